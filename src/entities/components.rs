@@ -1,4 +1,6 @@
-use crate::helpers::util::{GRAY, GREEN, YELLOW};
+use super::genome::{Genome, clamp_genes};
+use crate::helpers::util::{GRAY, GREEN, RED, YELLOW};
+use crate::resources::params::{GeneMutations, SimulationParams};
 use bevy::math::IVec2;
 use bevy::prelude::*;
 use bevy::utils::HashMap;
@@ -7,92 +9,256 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Resource, Default)]
 pub struct SpatialGrid {
-    pub buckets: HashMap<IVec2, Vec<Entity>>,
+    pub buckets: HashMap<IVec2, Vec<(Entity, Vec2)>>, // positions as of the last update
     pub cell_size: f32,
 }
 
+impl SpatialGrid {
+    pub fn cell(&self, pos: Vec2) -> IVec2 {
+        (pos / self.cell_size).floor().as_ivec2()
+    }
+
+    pub fn insert(&mut self, entity: Entity, pos: Vec2) {
+        let cell = self.cell(pos);
+        self.buckets.entry(cell).or_default().push((entity, pos));
+    }
+
+    // how far `pos` is from the closest point of a cell, 0 inside it
+    fn cell_distance(&self, cell: IVec2, pos: Vec2) -> f32 {
+        let min = cell.as_vec2() * self.cell_size;
+        let max = min + self.cell_size;
+        (min - pos).max(pos - max).max(Vec2::ZERO).length()
+    }
+
+    /// Everything within `radius` of `pos` as (entity, position, distance),
+    /// only visiting the cells the circle overlaps
+    pub fn within_radius(
+        &self,
+        pos: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = (Entity, Vec2, f32)> + '_ {
+        let min = self.cell(pos - radius);
+        let max = self.cell(pos + radius);
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter(move |&cell| self.cell_distance(cell, pos) <= radius)
+            .filter_map(|cell| self.buckets.get(&cell))
+            .flatten()
+            .filter_map(move |&(entity, other)| {
+                let dist = pos.distance(other);
+                (dist <= radius).then_some((entity, other, dist))
+            })
+    }
+
+    /// The closest entity within `radius` whose entity and position pass `filter`. Searches rings of cells
+    /// outward from `pos` and stops once no cell left can hold anything closer.
+    pub fn nearest(
+        &self,
+        pos: Vec2,
+        radius: f32,
+        mut filter: impl FnMut(Entity, Vec2) -> bool,
+    ) -> Option<(Entity, Vec2, f32)> {
+        let center = self.cell(pos);
+        let rings = (radius / self.cell_size).ceil() as i32 + 1;
+        let mut best: Option<(Entity, Vec2, f32)> = None;
+        for ring in 0..=rings {
+            let best_dist = best.map_or(radius, |(_, _, dist)| dist);
+            // everything in this ring is at least a ring minus one cell away
+            if (ring - 1) as f32 * self.cell_size > best_dist {
+                break;
+            }
+            for x in -ring..=ring {
+                for y in -ring..=ring {
+                    if x.abs() != ring && y.abs() != ring {
+                        continue; // inner rings are done already
+                    }
+                    let cell = center + IVec2::new(x, y);
+                    if self.cell_distance(cell, pos) > best_dist {
+                        continue;
+                    }
+                    let Some(entities) = self.buckets.get(&cell) else {
+                        continue;
+                    };
+                    for &(entity, other) in entities {
+                        let dist = pos.distance(other);
+                        let closer = match best {
+                            Some((_, _, best_dist)) => dist < best_dist,
+                            None => dist <= radius,
+                        };
+                        if closer && filter(entity, other) {
+                            best = Some((entity, other, dist));
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
 #[derive(Resource)]
 pub struct SimulationSpeed(pub f32);
 
+/// Simulated seconds since the world was created, carried over in save files
+#[derive(Resource, Default)]
+pub struct SimulationClock(pub f32);
+
+/// The id given to the newest herd, carried over in save files so ids stay unique
+#[derive(Resource, Default)]
+pub struct LastHerdId(pub u32);
+
 #[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct Genes {
     // personality traits (0.0 - 1.0 range)
     pub sociality: f32, // 0.0 = introvert, 1.0 = extrovert
     pub curiosity: f32, // how often it changes wander target
-    // pub boldness: f32,         // how close it dares approach predators
-    pub greed: f32,    // how far it goes for food or wants to eat
-    pub laziness: f32, // prefers resting vs exploring
-    // pub panic_threshold: f32,  // how easily it flees
-    // pub aggression: f32,       // relevant for predator
+    #[serde(default = "default_half")]
+    pub boldness: f32, // how close it dares approach predators
+    pub greed: f32,     // how far it goes for food or wants to eat
+    pub laziness: f32,  // prefers resting vs exploring
+    #[serde(default = "default_half")]
+    pub panic_threshold: f32, // how much fear it takes to flee
 
     // sense and physical limits
     pub vision_range: f32,
+    #[serde(default = "default_field_of_view")]
+    pub field_of_view: f32, // width of its vision cone in radians, wider ones see less far
     pub wander_radius: f32,
     pub max_speed: f32,
+    #[serde(default = "default_max_acceleration")]
+    pub max_acceleration: f32, // how fast it can change its velocity
+    #[serde(default = "default_max_turn_rate")]
+    pub max_turn_rate: f32, // how fast it can turn, in radians per second
     pub bite_size: f32,   // how much food it can eat at once
     pub hunger_rate: f32, // how much hunger it gains per second
     pub max_age: f32,     // how long the entity can live
+    #[serde(default = "default_sleep_duration")]
+    pub sleep_duration: f32, // how long it sleeps once it falls asleep
 
     pub gender: bool,             // true = female, false = male
     pub max_offspring_count: u32, // how many offspring the entity can produce
     pub can_produce_food: bool,   // whether the entity can produce food
+
+    // hunting, only used by predators
+    #[serde(default)]
+    pub aggression: f32, // 0.0 = hunts only when starving, 1.0 = hunts even when full
+    #[serde(default)]
+    pub attack_range: f32, // how close it has to get to kill its prey
+    #[serde(default)]
+    pub chase_speed: f32, // how fast it runs after its prey
 }
 
-impl Default for Genes {
-    fn default() -> Self {
-        let mut rng = rand::thread_rng();
+// genes added after the first save format, neither shy nor bold when missing
+fn default_half() -> f32 {
+    0.5
+}
+
+fn default_field_of_view() -> f32 {
+    std::f32::consts::PI
+}
+
+fn default_sleep_duration() -> f32 {
+    20.0
+}
+
+fn default_max_acceleration() -> f32 {
+    20.0
+}
+
+fn default_max_turn_rate() -> f32 {
+    4.0
+}
+
+impl Genes {
+    /// How much flesh the body leaves behind, bite size stands in for body size.
+    /// Young bodies haven't grown yet and starved ones have wasted away.
+    pub fn body_biomass(&self, age: f32, needs: &Needs, params: &SimulationParams) -> f32 {
+        let growth = (age / (self.max_age * params.adult_age)).clamp(0.1, 1.0);
+        let condition = 0.5 * needs.energy + 0.5 * (1.0 - needs.hunger / params.max_hunger);
+        params.biomass_per_size * self.bite_size * growth * condition.clamp(0.1, 1.0)
+    }
+
+    /// How far it sees inside its vision cone, a wide view costs range
+    pub fn sight_range(&self, params: &SimulationParams) -> f32 {
+        let width = (self.field_of_view / std::f32::consts::TAU).clamp(0.0, 1.0);
+        self.vision_range * (1.0 - params.wide_view_range_loss * width)
+    }
+
+    /// Whether something at `offset` from it is in sight, either inside the
+    /// vision cone around `heading` or close enough to notice from any side
+    pub fn in_view(&self, heading: Vec2, offset: Vec2, params: &SimulationParams) -> bool {
+        let dist = offset.length();
+        if dist > self.sight_range(params) {
+            return false;
+        }
+        // one that hasn't moved yet has no facing to limit it
+        dist <= params.peripheral_vision_radius
+            || heading == Vec2::ZERO
+            || heading.angle_between(offset).abs() <= self.field_of_view / 2.0
+    }
+
+    pub fn random(rng: &mut impl Rng) -> Self {
         let gender = rng.gen_bool(0.5);
         Self {
             sociality: rng.gen_range(0.0..1.0),
             vision_range: rng.gen_range(300.0..500.0),
+            field_of_view: rng.gen_range(1.0..std::f32::consts::TAU),
             laziness: rng.gen_range(0.0..1.0),
             greed: rng.gen_range(0.0..1.0),
             curiosity: rng.gen_range(0.0..1.0),
             wander_radius: rng.gen_range(300.0..600.0),
             bite_size: rng.gen_range(1.0..10.0),
             max_speed: rng.gen_range(5.0..10.0),
+            max_acceleration: rng.gen_range(10.0..30.0),
+            max_turn_rate: rng.gen_range(2.0..6.0),
             hunger_rate: rng.gen_range(0.5..1.0),
             max_age: rng.gen_range(100.0..300.0),
-            gender: gender,
+            sleep_duration: rng.gen_range(10.0..30.0),
+            gender,
             max_offspring_count: if gender { rng.gen_range(1..10) } else { 0 },
             can_produce_food: if gender { rng.gen_bool(0.5) } else { false },
-            // aggression: 0.0,
-            // boldness: 0.0,
-            // panic_threshold: 0.0,
+            aggression: 0.0,
+            attack_range: 0.0,
+            chase_speed: 0.0,
+            boldness: rng.gen_range(0.0..1.0),
+            panic_threshold: rng.gen_range(0.2..0.9),
         }
     }
-}
-impl Genes {
-    pub fn random_variation(&self) -> Genes {
-        let mut rng = rand::thread_rng();
-        let mut new_gene = self.clone();
+    pub fn random_predator(rng: &mut impl Rng) -> Self {
+        let mut genes = Self::random(rng);
+        genes.aggression = rng.gen_range(0.0..1.0);
+        genes.attack_range = rng.gen_range(2.0..5.0);
+        genes.chase_speed = rng.gen_range(8.0..14.0);
+        genes.max_acceleration = rng.gen_range(20.0..40.0); // quick to pounce
+        genes.hunger_rate = rng.gen_range(1.0..2.0); // meat runs out faster than plants
+        genes.can_produce_food = false;
+        genes
+    }
+    /// Close to these genes, within the valid range of each gene
+    pub fn random_variation(&self, mutations: &GeneMutations, rng: &mut impl Rng) -> Genes {
+        let mut new_gene = *self;
         new_gene.sociality = rng.gen_range(self.sociality - 0.1..self.sociality + 0.1);
+        new_gene.boldness = rng.gen_range(self.boldness - 0.1..self.boldness + 0.1);
+        new_gene.panic_threshold =
+            rng.gen_range(self.panic_threshold - 0.1..self.panic_threshold + 0.1);
         new_gene.vision_range = rng.gen_range(self.vision_range - 100.0..self.vision_range + 100.0);
+        new_gene.field_of_view = self.field_of_view;
         new_gene.wander_radius =
             rng.gen_range(self.wander_radius - 100.0..self.wander_radius + 100.0);
         new_gene.bite_size = self.bite_size;
         new_gene.max_speed = self.max_speed;
+        new_gene.max_acceleration = self.max_acceleration;
+        new_gene.max_turn_rate = self.max_turn_rate;
         new_gene.hunger_rate = self.hunger_rate;
         new_gene.max_age = self.max_age;
+        new_gene.sleep_duration = self.sleep_duration;
         new_gene.gender = rng.gen_bool(0.5);
         new_gene.max_offspring_count = self.max_offspring_count;
         new_gene.can_produce_food = self.can_produce_food;
-        new_gene
-    }
-    pub fn mutate(&self, father: &Genes) -> Genes {
-        let mut rng = rand::thread_rng();
-        let mut new_gene = self.clone();
-        new_gene.sociality = (self.sociality + father.sociality) / 2.0;
-        new_gene.vision_range = (self.vision_range + father.vision_range) / 2.0;
-        new_gene.wander_radius = (self.wander_radius + father.wander_radius) / 2.0;
-        new_gene.bite_size = (self.bite_size + father.bite_size) / 2.0;
-        new_gene.max_speed = (self.max_speed + father.max_speed) / 2.0;
-        new_gene.hunger_rate = (self.hunger_rate + father.hunger_rate) / 2.0;
-        new_gene.max_age = (self.max_age + father.max_age) / 2.0;
-        new_gene.gender = rng.gen_bool(0.5);
-        new_gene.max_offspring_count = (self.max_offspring_count + father.max_offspring_count) / 2;
-        new_gene.can_produce_food = self.can_produce_food || father.can_produce_food;
-        new_gene
+        new_gene.aggression = self.aggression;
+        new_gene.attack_range = self.attack_range;
+        new_gene.chase_speed = self.chase_speed;
+        clamp_genes(&new_gene, mutations)
     }
 }
 
@@ -111,9 +277,56 @@ pub struct Food;
 #[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct FoodAmount(pub f32); // How much food is left in the food entity
 
+/// Food that regrows and spreads seeds, unlike the food left by a corpse
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Plant {
+    pub area: Option<u32>, // forest area it belongs to, plants outside any area don't seed
+    pub max_amount: f32,   // how much food it regrows up to
+    pub seed_timer: f32,   // time until it can spread a seed again
+}
+
+/// A seed picked up while eating, planted where the creature is when the timer runs out
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct CarriedSeed {
+    pub area: Option<u32>, // forest area of the plant it came from
+    pub max_amount: f32,   // the seedling grows as big as its parent
+    pub timer: f32,
+}
+
+/// A forest area plants can seed in, up to its carrying capacity
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ForestArea {
+    pub id: u32,
+    pub center: Vec2,
+    pub half_size: f32,
+    pub capacity: u32, // most plants the area can carry
+}
+
+impl ForestArea {
+    pub fn contains(&self, pos: Vec2) -> bool {
+        (pos - self.center).abs().max_element() <= self.half_size
+    }
+}
+
 #[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct Predator;
 
+#[derive(Component, Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct Velocity(pub Vec2);
+
+/// Direction it last moved in, zero until it moves
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct Heading(pub Vec2);
+
+/// Herd it belongs to, worked out again every step by `herd_system`
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct Herd {
+    pub id: Option<u32>,        // None for loners
+    pub size: u32,              // members including itself, 1 for loners
+    pub leader: Option<Entity>, // the oldest member
+    pub steering: Vec2,         // cohesion, alignment and separation, weighted by sociality
+}
+
 #[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct LivingEntity;
 
@@ -137,14 +350,26 @@ pub struct EntityColor(pub Color);
 pub enum BehaviorState {
     SeekFood,
     Sleep,
-    // Flee,
+    Flee,
     Wander,
 }
 
+/// Utility of every behavior from the last decision, see `decision_system`
+#[derive(Component, Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct BehaviorScores {
+    pub seek_food: f32,
+    pub sleep: f32,
+    pub flee: f32,
+    pub wander: f32,
+}
+
 #[derive(Component, Default, Clone)]
 pub struct Perception {
     pub target_food: Option<Entity>,
+    pub target_prey: Option<Entity>,   // predators only
+    pub target_corpse: Option<Entity>, // predators and starving prey
     pub visible_predators: Vec<Entity>,
+    pub threats: Vec<(Vec2, f32)>, // (position, strength) of the visible predators
     // pub nearby_predator: bool,
     pub time_since_last_sense: f32,
     pub neighbors: Vec<Vec2>,
@@ -163,36 +388,40 @@ pub struct Perception {
 
 #[derive(Component)]
 pub struct Needs {
-    // pub fear: f32,   // high fear = slower movement
+    pub fear: f32,   // builds up near threats, flees above the panic threshold
     pub sanity: f32, // low sanity = more aggressive
 
-    pub hunger: f32, // hunger should influence sanity
-    pub energy: f32, // low energy = slower movement
+    pub hunger: f32,      // hunger should influence sanity
+    pub energy: f32,      // low energy = slower movement
+    pub sleep_timer: f32, // how much longer it sleeps unless something wakes it
 
     // reproduction related
-    pub mother: Option<Entity>,       // the entity it is mother of
-    pub pregnancy_timer: f32,         // how long the entity has been pregnant
-    pub pregnant: bool,               // whether the entity is pregnant
-    pub mating_timer: f32,            // how long is it staying with a partner before mating
-    pub partner: Option<Entity>,      // the entity it is mating with
-    pub partner_genes: Option<Genes>, // the genes of the partner
-    pub partner_timer: f32,           // how long stay together being partners
-    pub mate_ready: bool,             // whether the entity is ready to mate
+    pub mother: Option<Entity>,           // the entity it is mother of
+    pub pregnancy_timer: f32,             // how long the entity has been pregnant
+    pub pregnant: bool,                   // whether the entity is pregnant
+    pub mating_timer: f32,                // how long is it staying with a partner before mating
+    pub partner: Option<Entity>,          // the entity it is mating with
+    pub partner_genome: Option<Genome>,   // the genome of the partner
+    pub partner_id: Option<IndividualId>, // the partner in the lineage, outlives its entity
+    pub partner_timer: f32,               // how long stay together being partners
+    pub mate_ready: bool,                 // whether the entity is ready to mate
 }
 
 impl Default for Needs {
     fn default() -> Self {
         Self {
-            // fear: 0.0,
+            fear: 0.0,
             sanity: 1.0,
             hunger: 0.0,
             energy: 1.0,
+            sleep_timer: 0.0,
             mother: None,
             pregnancy_timer: 0.0,
             pregnant: false,
             mating_timer: 0.0,
             partner: None,
-            partner_genes: None,
+            partner_genome: None,
+            partner_id: None,
             partner_timer: 0.0,
             mate_ready: false,
         }
@@ -202,17 +431,41 @@ impl Default for Needs {
 #[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct SpeciesId(pub u32);
 
-// and entities can vary a little bit from the genetic config
-#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+/// A creature's entry in the lineage ledger, unlike its `Entity` it's never reused
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IndividualId(pub u64);
+
+// and entities can vary a little bit from the genetic config,
+// every species that ever lived is kept in the `SpeciesRegistry`
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Species {
     pub id: SpeciesId,
     pub genetic_min: Genes,
     pub genetic_max: Genes,
+    // the rest was added in save version 12
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub color: (f32, f32, f32), // its creatures are drawn in this srgb color
+    #[serde(default)]
+    pub predator: bool,
+    #[serde(default)]
+    pub parent: Option<SpeciesId>, // the species it split off from
+    #[serde(default)]
+    pub population: u32, // living members as of the last census
+    #[serde(default)]
+    pub founded: f32, // simulation clock when it appeared
+    #[serde(default)]
+    pub extinct: Option<f32>, // simulation clock when its last member died
 }
 
 impl Species {
-    pub fn random_gene(&self) -> Genes {
-        self.genetic_min.mutate(&self.genetic_max)
+    pub fn color(&self) -> Color {
+        Color::srgb(self.color.0, self.color.1, self.color.2)
+    }
+
+    pub fn random_genome(&self, mutations: &GeneMutations, rng: &mut impl Rng) -> Genome {
+        Genome::random_between(&self.genetic_min, &self.genetic_max, mutations, rng)
     }
 }
 
@@ -224,7 +477,7 @@ pub fn create_food(
     Food,
     WorldObject,
     EntityColor,
-    SpriteBundle,
+    Transform,
     FoodAmount,
 ) {
     (
@@ -232,21 +485,37 @@ pub fn create_food(
         Food,
         WorldObject,
         EntityColor(GREEN),
-        SpriteBundle {
-            sprite: Sprite {
-                color: GREEN,
-                custom_size: Some(Vec2::new(2.0, 2.0)), // Smaller radius ~3
-                ..default()
-            },
-            transform: Transform::from_translation(pos.extend(0.0)),
-            ..default()
-        },
+        Transform::from_translation(pos.extend(0.0)),
         FoodAmount(amount),
     )
 }
+pub fn create_plant(
+    pos: Vec2,
+    amount: f32,
+    area: Option<u32>,
+) -> (
+    (
+        Position,
+        Food,
+        WorldObject,
+        EntityColor,
+        Transform,
+        FoodAmount,
+    ),
+    Plant,
+) {
+    (
+        create_food(pos, amount),
+        Plant {
+            area,
+            max_amount: amount,
+            seed_timer: 0.0,
+        },
+    )
+}
 pub fn create_prey(
     pos: Vec2,
-    speciesId: SpeciesId,
+    species_id: SpeciesId,
     gene: Genes,
 ) -> (
     Position,
@@ -256,11 +525,14 @@ pub fn create_prey(
     EntityColor,
     BehaviorState,
     Genes,
-    SpriteBundle,
+    Transform,
     Perception,
     Age,
     Needs,
     SpeciesId,
+    (Velocity, Heading),
+    Herd,
+    BehaviorScores,
 ) {
     (
         Position(pos),
@@ -270,42 +542,78 @@ pub fn create_prey(
         EntityColor(YELLOW),
         BehaviorState::Wander,
         gene,
-        SpriteBundle {
-            sprite: Sprite {
-                color: YELLOW,
-                custom_size: Some(Vec2::new(2.0, 2.0)),
-                ..default()
-            },
-            transform: Transform::from_translation(pos.extend(0.0)),
-            ..default()
-        },
+        Transform::from_translation(pos.extend(0.0)),
         Perception::default(),
         Age(0.0),
         Needs::default(),
-        speciesId,
+        species_id,
+        (Velocity::default(), Heading::default()),
+        Herd::default(),
+        BehaviorScores::default(),
     )
 }
 pub fn create_corpse(
     pos: Vec2,
     flesh_amount: f32,
-) -> (Position, Corpse, WorldObject, SpriteBundle, CorpseState) {
+    decay_time: f32,
+    decay_rate: f32,
+) -> (
+    Position,
+    Corpse,
+    WorldObject,
+    EntityColor,
+    Transform,
+    CorpseState,
+) {
     (
         Position(pos),
         Corpse,
         WorldObject,
-        SpriteBundle {
-            sprite: Sprite {
-                color: GRAY,
-                custom_size: Some(Vec2::new(2.0, 2.0)),
-                ..default()
-            },
-            transform: Transform::from_translation(pos.extend(0.0)),
-            ..default()
-        },
+        EntityColor(GRAY),
+        Transform::from_translation(pos.extend(0.0)),
         CorpseState {
-            flesh_amount: flesh_amount,
-            decay_rate: 1.0,
-            decay_timer: 100.0,
+            flesh_amount,
+            decay_rate,
+            decay_timer: decay_time,
         },
     )
 }
+pub fn create_predator(
+    pos: Vec2,
+    species_id: SpeciesId,
+    gene: Genes,
+) -> (
+    Position,
+    Predator,
+    WorldObject,
+    LivingEntity,
+    EntityColor,
+    BehaviorState,
+    Genes,
+    Transform,
+    Perception,
+    Age,
+    Needs,
+    SpeciesId,
+    (Velocity, Heading),
+    Herd,
+    BehaviorScores,
+) {
+    (
+        Position(pos),
+        Predator,
+        WorldObject,
+        LivingEntity,
+        EntityColor(RED),
+        BehaviorState::Wander,
+        gene,
+        Transform::from_translation(pos.extend(0.0)),
+        Perception::default(),
+        Age(0.0),
+        Needs::default(),
+        species_id,
+        (Velocity::default(), Heading::default()),
+        Herd::default(),
+        BehaviorScores::default(),
+    )
+}
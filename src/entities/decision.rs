@@ -0,0 +1,128 @@
+use super::components::{
+    BehaviorScores, BehaviorState, Genes, LivingEntity, Needs, Perception, Predator,
+};
+use crate::resources::params::SimulationParams;
+use bevy::prelude::*;
+
+// 0 well below `mid`, 1 well above it, 0.5 right at it
+fn logistic(x: f32, mid: f32, steepness: f32) -> f32 {
+    1.0 / (1.0 + (-steepness * (x - mid)).exp())
+}
+
+impl BehaviorScores {
+    /// How much it wants each behavior right now, every score is roughly 0.0 - 1.0
+    /// except fleeing, which outgrows everything once fear passes the panic threshold.
+    pub fn score(
+        needs: &Needs,
+        genes: &Genes,
+        perception: &Perception,
+        is_predator: bool,
+        params: &SimulationParams,
+    ) -> Self {
+        let hunger = needs.hunger / params.max_hunger;
+        // greedy eaters and aggressive hunters want food long before they starve
+        let food_hunger = if is_predator {
+            params.predator_hunt_hunger * (1.0 - genes.aggression)
+        } else {
+            params.seek_food_hunger * (1.5 - genes.greed)
+        };
+        let seek_food = logistic(
+            hunger,
+            food_hunger / params.max_hunger,
+            params.seek_food_steepness,
+        );
+
+        let flee = (needs.fear / genes.panic_threshold.max(0.01))
+            .powi(3)
+            .min(2.0);
+
+        // tired or troubled ones want rest, but not when hungry or something is watching
+        let tiredness = (1.0 - needs.energy).max(0.5 * (1.0 - needs.sanity));
+        let sleep = if perception.threats.is_empty() {
+            tiredness * (0.5 + genes.laziness) * (1.0 - hunger)
+        } else {
+            0.0
+        };
+
+        // curious and restless ones roam, and so do those looking for a mate
+        let mut wander = 0.2 + 0.2 * genes.curiosity + 0.2 * (1.0 - needs.sanity);
+        if needs.mate_ready && perception.nearby_mates.is_empty() {
+            wander += 0.2;
+        }
+        wander *= 1.0 - 0.5 * genes.laziness;
+
+        Self {
+            seek_food,
+            sleep,
+            flee,
+            wander,
+        }
+    }
+
+    pub fn get(&self, state: &BehaviorState) -> f32 {
+        match state {
+            BehaviorState::SeekFood => self.seek_food,
+            BehaviorState::Sleep => self.sleep,
+            BehaviorState::Flee => self.flee,
+            BehaviorState::Wander => self.wander,
+        }
+    }
+
+    /// The best scoring behavior, the current one has to be beaten by more than
+    /// `hysteresis` so it doesn't flicker between two close scores.
+    pub fn best(&self, current: &BehaviorState, hysteresis: f32) -> BehaviorState {
+        let mut best = current.clone();
+        let mut best_score = self.get(current) + hysteresis;
+        // ties go to the more urgent behavior
+        for state in [
+            BehaviorState::Flee,
+            BehaviorState::SeekFood,
+            BehaviorState::Sleep,
+            BehaviorState::Wander,
+        ] {
+            let score = self.get(&state);
+            if score > best_score {
+                best_score = score;
+                best = state;
+            }
+        }
+        best
+    }
+}
+
+// picks the behavior with the highest utility, the scores stay on the entity for debugging
+pub fn decision_system(
+    mut query: Query<
+        (
+            &mut Needs,
+            &Genes,
+            &Perception,
+            &mut BehaviorState,
+            &mut BehaviorScores,
+            Has<Predator>,
+        ),
+        With<LivingEntity>,
+    >,
+    params: Res<SimulationParams>,
+) {
+    for (mut needs, genes, perception, mut behavior_state, mut scores, is_predator) in
+        query.iter_mut()
+    {
+        *scores = BehaviorScores::score(&needs, genes, perception, is_predator, &params);
+        if *behavior_state == BehaviorState::Sleep {
+            // sleeps its time out unless a threat wakes it up
+            if needs.sleep_timer > 0.0 && perception.threats.is_empty() {
+                continue;
+            }
+            needs.sleep_timer = 0.0;
+        }
+        let best = scores.best(&behavior_state, params.behavior_hysteresis);
+        // only touch the state when it changes, so change detection means something
+        if *behavior_state != best {
+            if best == BehaviorState::Sleep {
+                needs.sleep_timer = genes.sleep_duration;
+            }
+            *behavior_state = best;
+        }
+    }
+}
@@ -1,115 +1,210 @@
 use super::components::{
-    Age, BehaviorState, Food, FoodAmount, Genes, LivingEntity, Needs, Perception, Position, Prey,
-    SimulationSpeed, create_corpse,
+    BehaviorState, CarriedSeed, Corpse, CorpseState, Food, FoodAmount, Genes, Heading, Herd,
+    IndividualId, LivingEntity, Needs, Perception, Plant, Position, Predator, Velocity,
 };
+use super::genome::Genome;
+use super::steering::{Kinematics, arrive, flee, separation, steer, wander};
+use crate::resources::params::SimulationParams;
+use crate::resources::rng::{RngStream, SimRng};
 use bevy::prelude::*;
 use rand::Rng;
 
-const COLLISION_RADIUS: f32 = 4.0;
-const COLLISION_REPULSION_STRENGTH: f32 = 50.0;
-
-const CORPSE_FEAR_RADIUS: f32 = 10.0;
 // const CORPSE_FEAR_REPULSION_STRENGTH: f32 = 100.0;
 
+#[allow(dead_code)] // for the mate attraction below, which is switched off
 const MATE_ATTRACTION_RADIUS: f32 = 100.0;
+#[allow(dead_code)]
 const MATE_ATTRACTION_STRENGTH: f32 = 10.0;
 
 pub fn game_loop(
     mut commands: Commands,
-    mut prey_query: Query<(&mut Position, &mut Needs, &Genes, &Perception), With<Prey>>,
-    mut food_query: Query<(Entity, &Transform, &mut FoodAmount), With<Food>>,
-    lookup_gene_query: Query<&Genes, With<LivingEntity>>, //conflict
+    // predators share wandering, avoidance and mating, chasing is done in `hunt_system`
+    mut living_query: Query<
+        (
+            Entity,
+            &mut Position,
+            &mut Needs,
+            &Genes,
+            &Perception,
+            &BehaviorState,
+            &Herd,
+            &mut Velocity,
+            &mut Heading,
+            Has<Predator>,
+            Has<CarriedSeed>,
+        ),
+        With<LivingEntity>,
+    >,
+    mut food_query: Query<
+        (Entity, &Position, &mut FoodAmount, Option<&Plant>),
+        (With<Food>, Without<LivingEntity>),
+    >,
+    mut corpse_query: Query<
+        (Entity, &Position, &mut CorpseState),
+        (With<Corpse>, Without<LivingEntity>),
+    >,
+    lookup_genome_query: Query<(&Genome, Option<&IndividualId>), With<LivingEntity>>,
     // lookup_pos_query: Query<&Position, With<LivingEntity>>, //conflict
     time: Res<Time>,
-    simulation_speed: Res<SimulationSpeed>,
+    mut sim_rng: ResMut<SimRng>,
+    params: Res<SimulationParams>,
 ) {
     let mut foods_to_delete = Vec::new();
-    let mut rng = rand::thread_rng();
+    let mut corpses_to_delete = Vec::new();
+    let rng = sim_rng.stream(RngStream::GameLoop);
 
-    for (mut prey_pos, mut needs, genes, perception) in prey_query.iter_mut() {
-        let delta_time = time.delta_seconds() * simulation_speed.0;
+    for (
+        entity,
+        mut pos,
+        mut needs,
+        genes,
+        perception,
+        behavior_state,
+        herd,
+        mut velocity,
+        mut heading,
+        is_predator,
+        carries_seed,
+    ) in living_query.iter_mut()
+    {
+        // sleepers lie still, neither eating nor mating
+        if *behavior_state == BehaviorState::Sleep {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+        // steered by `hunt_system` while it chases
+        if perception.target_prey.is_some() {
+            continue;
+        }
+        let delta_time = time.delta_seconds();
+        let fleeing = *behavior_state == BehaviorState::Flee;
 
-        let mut nearest_food_pos = None;
-        if let Some(food) = perception.target_food {
-            if !foods_to_delete.contains(&food) {
-                if let Ok((food_entity, food_transform, mut food_amount)) = food_query.get_mut(food)
-                {
-                    let food_pos = food_transform.translation.truncate();
-                    let distance = prey_pos.0.distance(food_pos);
-                    if distance <= 2.5 {
-                        let amount_eaten = genes.bite_size.min(food_amount.0);
-                        food_amount.0 -= amount_eaten;
-                        needs.hunger = (needs.hunger - amount_eaten).clamp(0.0, 100.0);
-                        needs.energy += amount_eaten / 100.0; // eating food = energy gain
-                        if food_amount.0 <= 0.0 {
-                            // do not delete the food entity here, just add it to the list of foods to delete
-                            // so others don't try to delete it again
-                            foods_to_delete.push(food_entity);
-                        }
-                    } else {
-                        nearest_food_pos = Some(food_pos);
-                    }
+        // where the food or corpse it is going to eat is
+        let mut meal_pos = None;
+        let target_food = perception
+            .target_food
+            .filter(|food| !fleeing && !foods_to_delete.contains(food));
+        if let Some((food_entity, food_pos, mut food_amount, plant)) =
+            target_food.and_then(|food| food_query.get_mut(food).ok())
+        {
+            let food_pos = food_pos.0;
+            let distance = pos.0.distance(food_pos);
+            if distance <= 2.5 {
+                let amount_eaten = genes.bite_size.min(food_amount.0);
+                food_amount.0 -= amount_eaten;
+                needs.hunger = (needs.hunger - amount_eaten).clamp(0.0, params.max_hunger);
+                needs.energy += amount_eaten / 100.0; // eating food = energy gain
+                // food producers take a seed along, see `seed_dispersal_system`
+                if let Some(plant) = plant.filter(|_| genes.can_produce_food && !carries_seed) {
+                    commands.entity(entity).insert(CarriedSeed {
+                        area: plant.area,
+                        max_amount: plant.max_amount,
+                        timer: params.seed_carry_time,
+                    });
+                }
+                if food_amount.0 <= 0.0 {
+                    // do not delete the food entity here, just add it to the list of foods to delete
+                    // so others don't try to delete it again
+                    foods_to_delete.push(food_entity);
                 }
+            } else {
+                meal_pos = Some(food_pos);
+            }
+        }
+        // scavenge when there is no food to go for
+        let mut scavenged_corpse_pos = None;
+        let target_corpse = perception
+            .target_corpse
+            .filter(|corpse| !fleeing && meal_pos.is_none() && !corpses_to_delete.contains(corpse));
+        if let Some((corpse_entity, corpse_pos, mut corpse_state)) =
+            target_corpse.and_then(|corpse| corpse_query.get_mut(corpse).ok())
+        {
+            let corpse_pos = corpse_pos.0;
+            scavenged_corpse_pos = Some(corpse_pos);
+            let distance = pos.0.distance(corpse_pos);
+            if distance <= 2.5 {
+                let amount_eaten = genes.bite_size.min(corpse_state.flesh_amount);
+                corpse_state.flesh_amount -= amount_eaten;
+                // fresh flesh is worth more, rotten flesh makes it sick
+                let freshness =
+                    (corpse_state.decay_timer / params.corpse_decay_time).clamp(0.0, 1.0);
+                let nutrition = amount_eaten * (0.5 + 0.5 * freshness);
+                needs.hunger = (needs.hunger - nutrition).clamp(0.0, params.max_hunger);
+                needs.energy += nutrition / 100.0;
+                if freshness < params.rotten_freshness {
+                    needs.sanity -= amount_eaten * params.rotten_sanity_loss;
+                }
+                if corpse_state.flesh_amount <= 0.0 {
+                    corpses_to_delete.push(corpse_entity);
+                }
+            } else {
+                meal_pos = Some(corpse_pos);
             }
         }
         let mut speed = genes.max_speed * needs.energy;
-        if needs.sanity < 0.3 && rand::random::<f32>() > 0.5 {
+        if needs.sanity < 0.3 && rng.gen_bool(0.5) {
             speed *= 1.0 - needs.sanity;
         }
-        let mut desired_velocity = Vec2::ZERO;
+        let mut steering_force = Vec2::ZERO;
+        let mut max_speed = speed;
         // TODO: hunger should also influence sanity, and energy
-        if let Some(food_pos) = nearest_food_pos {
-            // Move toward nearest food
-            let direction = (food_pos - prey_pos.0).normalize_or_zero();
+        if fleeing {
+            // run away from everything scary at full speed, stronger threats pull harder
+            max_speed = genes.max_speed;
+            for &(threat_pos, strength) in perception.threats.iter() {
+                steering_force += flee(pos.0, velocity.0, threat_pos, max_speed) * strength;
+            }
+            for &(corpse_pos, stench) in perception.nearby_corpses.iter() {
+                let stench_radius = params.corpse_fear_radius * (1.0 + stench);
+                if pos.0.distance(corpse_pos) <= stench_radius {
+                    steering_force += flee(pos.0, velocity.0, corpse_pos, max_speed)
+                        * stench.min(1.0)
+                        * params.corpse_fear_weight;
+                }
+            }
+        } else if let Some(food_pos) = meal_pos {
             // more hungry = more speed
-            let move_distance = (genes.max_speed * needs.hunger / 100.0) * delta_time;
-            desired_velocity = direction * move_distance;
+            max_speed = genes.max_speed * needs.hunger / params.max_hunger;
+            steering_force += arrive(
+                pos.0,
+                velocity.0,
+                food_pos,
+                max_speed,
+                params.arrive_slowing_radius,
+            );
         } else if let Some(target) = perception.target {
-            let direction = (target - prey_pos.0).normalize_or_zero();
-            let move_distance = speed * delta_time; // Move at wander speed
-            desired_velocity = direction * move_distance;
+            steering_force += arrive(
+                pos.0,
+                velocity.0,
+                target,
+                max_speed,
+                params.arrive_slowing_radius,
+            );
+        } else if *behavior_state == BehaviorState::Wander {
+            // between two wander targets
+            steering_force += wander(velocity.0, heading.0, max_speed, rng);
+        } else {
+            // nowhere to go, slows down to a stop
+            steering_force -= velocity.0;
         }
 
         // always avoid neighbors, cascade movement
-        let mut avoidance_force = Vec2::ZERO;
-        for &neighbor_pos in perception.neighbors.iter() {
-            // its the food I am going to eat, so I should not avoid it
-            if let Some(food_pos) = nearest_food_pos {
-                if neighbor_pos == food_pos {
-                    continue;
-                }
-            }
-            let distance = prey_pos.0.distance(neighbor_pos);
-            let repulsion_direction = (prey_pos.0 - neighbor_pos).normalize_or_zero();
-            let strength = (COLLISION_RADIUS - distance) / COLLISION_RADIUS; // Stronger when closer
-            avoidance_force +=
-                repulsion_direction * strength * COLLISION_REPULSION_STRENGTH * delta_time;
-
-            // Add tangential force for going around
-            // randomly choose the tangent direction
-            let tangent = if rand::random::<f32>() > 0.5 {
-                Vec2::new(-repulsion_direction.y, repulsion_direction.x)
-            } else {
-                Vec2::new(repulsion_direction.y, -repulsion_direction.x)
-            };
+        // its the food I am going to eat, so I should not avoid it
+        let neighbors: Vec<Vec2> = perception
+            .neighbors
+            .iter()
+            .copied()
+            .filter(|&neighbor_pos| meal_pos != Some(neighbor_pos))
+            .collect();
+        steering_force += separation(pos.0, &neighbors, params.collision_radius)
+            * params.collision_repulsion_strength;
 
-            // Choose tangent direction based on desired movement
-            let desired_movement_direction = desired_velocity.normalize_or_zero();
-            let dot_product = tangent.dot(desired_movement_direction);
-            let tangent_direction = if dot_product >= 0.0 {
-                tangent
-            } else {
-                -tangent
-            };
-            // accumulate the tangent force for each neighbor
-            avoidance_force += tangent_direction * delta_time;
-        }
         // check if there is a nearby mate
-        if genes.gender == false {
+        if !genes.gender {
             let nearby_mates_count = perception.nearby_mates.len();
             // for females only
             for &mate_entity in perception.nearby_mates.iter() {
-                if let Ok(mate_genes) = lookup_gene_query.get(mate_entity) {
+                if let Ok((mate_genome, mate_id)) = lookup_genome_query.get(mate_entity) {
                     if nearby_mates_count == 1 {
                         needs.mating_timer += delta_time + rng.gen_range(0.1..0.2);
                     } else {
@@ -121,43 +216,58 @@ pub fn game_loop(
                         needs.partner_timer = rng.gen_range(10.0..30.0);
                         needs.pregnant = true;
                         needs.pregnancy_timer = rng.gen_range(10.0..30.0);
-                        needs.partner_genes = Some(mate_genes.clone());
+                        needs.partner_genome = Some(*mate_genome);
+                        needs.partner_id = mate_id.copied();
                         break;
                     }
                 }
             }
         }
-        let mut mate_attraction_force = Vec2::ZERO;
         // if let Some(mate_partner) = needs.partner {
         //     if let Ok(mate_pos) = lookup_pos_query.get(mate_partner) {
-        //         let distance = prey_pos.0.distance(mate_pos.0);
-        //         let attraction_direction = (mate_pos.0 - prey_pos.0).normalize_or_zero();
-        //         let strength = (MATE_ATTRACTION_RADIUS - distance) / MATE_ATTRACTION_RADIUS; // Stronger when closer
-        //         mate_attraction_force +=
-        //             attraction_direction * strength * MATE_ATTRACTION_STRENGTH * delta_time;
+        //         steering_force += seek(pos.0, velocity.0, mate_pos.0, max_speed) * MATE_ATTRACTION_STRENGTH;
         //     }
         // }
 
-        // avoid corpses
-        let mut corpse_avoidance_force = Vec2::ZERO;
-        for &(corpse_pos, stench) in perception.nearby_corpses.iter() {
-            let distance = prey_pos.0.distance(corpse_pos);
-            let stench_radius = CORPSE_FEAR_RADIUS + (CORPSE_FEAR_RADIUS * stench);
-            if distance > stench_radius {
+        // avoid corpses, carnivores don't mind them
+        let scary_corpses: &[(Vec2, f32)] = if is_predator {
+            &[]
+        } else {
+            &perception.nearby_corpses
+        };
+        for &(corpse_pos, stench) in scary_corpses.iter() {
+            let distance = pos.0.distance(corpse_pos);
+            let stench_radius = params.corpse_fear_radius + (params.corpse_fear_radius * stench);
+            // the corpse it is eating doesn't scare it away
+            if distance > stench_radius || scavenged_corpse_pos == Some(corpse_pos) {
                 continue;
             }
             needs.sanity -= stench * delta_time;
-            let repulsion_direction = (prey_pos.0 - corpse_pos).normalize_or_zero();
-            let strength = (stench_radius - distance) / stench_radius; // Stronger when closer
-            corpse_avoidance_force +=
-                repulsion_direction * strength * COLLISION_REPULSION_STRENGTH * delta_time;
+            steering_force += separation(pos.0, &[corpse_pos], stench_radius)
+                * params.collision_repulsion_strength;
+        }
+
+        // keep with the herd unless going for a meal
+        if meal_pos.is_none() {
+            steering_force += herd.steering * max_speed;
         }
 
-        prey_pos.0 +=
-            desired_velocity + avoidance_force + corpse_avoidance_force + mate_attraction_force;
+        velocity.0 = steer(
+            velocity.0,
+            steering_force,
+            Kinematics::new(genes, max_speed),
+            delta_time,
+        );
+        pos.0 += velocity.0 * delta_time;
+        if velocity.0 != Vec2::ZERO {
+            heading.0 = velocity.0.normalize();
+        }
     }
     // Delete foods that are no longer needed
     for food_entity in foods_to_delete.iter_mut() {
         commands.entity(*food_entity).despawn();
     }
+    for corpse_entity in corpses_to_delete.iter() {
+        commands.entity(*corpse_entity).despawn();
+    }
 }
@@ -0,0 +1,358 @@
+use super::components::Genes;
+use crate::resources::params::{GeneMutation, GeneMutations, MutationStep, SimulationParams};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// What kind of value a locus holds, which decides how it is expressed and mutated
+#[derive(Clone, Copy, PartialEq)]
+enum LocusKind {
+    Trait,  // any number
+    Count,  // a whole number, expressed rounded
+    Switch, // on or off, expressed on at 0.5 and above
+}
+
+/// One gene position, the same in both strands of a genome
+struct Locus {
+    kind: LocusKind,
+    // 0.0 = the lower allele is dominant, 1.0 = the higher one is, 0.5 = both count the same
+    dominance: f32,
+    get: fn(&Genes) -> f32,
+    set: fn(&mut Genes, f32),
+    mutation: fn(&GeneMutations) -> &GeneMutation,
+}
+
+// a standard normal sample, from two uniform ones (Box-Muller)
+fn standard_normal(rng: &mut impl Rng) -> f32 {
+    let radius = (-2.0 * (1.0 - rng.gen_range(0.0f32..1.0)).ln()).sqrt();
+    radius * (std::f32::consts::TAU * rng.gen_range(0.0f32..1.0)).cos()
+}
+
+impl Locus {
+    fn mutate(&self, value: f32, mutations: &GeneMutations, rng: &mut impl Rng) -> f32 {
+        let mutation = (self.mutation)(mutations);
+        let value = match (self.kind, mutation.step) {
+            (LocusKind::Switch, _) => 1.0 - value,
+            (_, MutationStep::Gaussian(deviation)) => value + standard_normal(rng) * deviation,
+            (_, MutationStep::Uniform(size)) => value + rng.gen_range(-size..=size),
+            (_, MutationStep::Creep(size)) => value + if rng.gen_bool(0.5) { size } else { -size },
+        };
+        self.clamp(value, mutations)
+    }
+
+    fn clamp(&self, value: f32, mutations: &GeneMutations) -> f32 {
+        let value = (self.mutation)(mutations).clamp(value);
+        if self.kind == LocusKind::Trait {
+            value
+        } else {
+            value.round()
+        }
+    }
+}
+
+/// The genes with every value moved into its valid range
+pub fn clamp_genes(genes: &Genes, mutations: &GeneMutations) -> Genes {
+    let mut clamped = *genes;
+    for locus in LOCI.iter() {
+        (locus.set)(&mut clamped, locus.clamp((locus.get)(genes), mutations));
+    }
+    clamped
+}
+
+/// The lowest and highest value of every gene among `genes`, e.g. the range of a species
+pub fn gene_range<'a>(genes: impl IntoIterator<Item = &'a Genes>) -> Option<(Genes, Genes)> {
+    let mut genes = genes.into_iter();
+    let first = *genes.next()?;
+    let (mut min, mut max) = (first, first);
+    for other in genes {
+        for locus in LOCI.iter() {
+            let value = (locus.get)(other);
+            let (lowest, highest) = ((locus.get)(&min), (locus.get)(&max));
+            (locus.set)(&mut min, lowest.min(value));
+            (locus.set)(&mut max, highest.max(value));
+        }
+    }
+    min.gender = false;
+    max.gender = true;
+    Some((min, max))
+}
+
+/// The average of every gene among `genes`
+pub fn mean_genes<'a>(genes: impl IntoIterator<Item = &'a Genes>) -> Option<Genes> {
+    let mut genes = genes.into_iter();
+    let mut mean = *genes.next()?;
+    let mut totals: Vec<f32> = LOCI.iter().map(|locus| (locus.get)(&mean)).collect();
+    let mut count = 1.0;
+    for other in genes {
+        for (total, locus) in totals.iter_mut().zip(LOCI.iter()) {
+            *total += (locus.get)(other);
+        }
+        count += 1.0;
+    }
+    for (total, locus) in totals.iter().zip(LOCI.iter()) {
+        (locus.set)(&mut mean, total / count);
+    }
+    Some(mean)
+}
+
+/// How different two sets of genes are, the mean difference of every gene relative to
+/// its valid range. 0.0 = the same genes, 1.0 = opposite ends of every range
+pub fn genetic_distance(a: &Genes, b: &Genes, mutations: &GeneMutations) -> f32 {
+    let total: f32 = LOCI
+        .iter()
+        .map(|locus| {
+            let bounds = (locus.mutation)(mutations);
+            let difference = ((locus.get)(a) - (locus.get)(b)).abs();
+            (difference / (bounds.max - bounds.min).max(f32::EPSILON)).min(1.0)
+        })
+        .sum();
+    total / LOCI.len() as f32
+}
+
+// loci in strand order, neighbors are more likely to be inherited together.
+// sex is not a locus, it is decided at conception
+const LOCI: [Locus; 21] = [
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.sociality,
+        set: |genes, value| genes.sociality = value,
+        mutation: |mutations| &mutations.sociality,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.curiosity,
+        set: |genes, value| genes.curiosity = value,
+        mutation: |mutations| &mutations.curiosity,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.boldness,
+        set: |genes, value| genes.boldness = value,
+        mutation: |mutations| &mutations.boldness,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.greed,
+        set: |genes, value| genes.greed = value,
+        mutation: |mutations| &mutations.greed,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.laziness,
+        set: |genes, value| genes.laziness = value,
+        mutation: |mutations| &mutations.laziness,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.3, // a calmer allele mostly wins
+        get: |genes| genes.panic_threshold,
+        set: |genes, value| genes.panic_threshold = value,
+        mutation: |mutations| &mutations.panic_threshold,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.7,
+        get: |genes| genes.vision_range,
+        set: |genes, value| genes.vision_range = value,
+        mutation: |mutations| &mutations.vision_range,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.field_of_view,
+        set: |genes, value| genes.field_of_view = value,
+        mutation: |mutations| &mutations.field_of_view,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.wander_radius,
+        set: |genes, value| genes.wander_radius = value,
+        mutation: |mutations| &mutations.wander_radius,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.7,
+        get: |genes| genes.max_speed,
+        set: |genes, value| genes.max_speed = value,
+        mutation: |mutations| &mutations.max_speed,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.max_acceleration,
+        set: |genes, value| genes.max_acceleration = value,
+        mutation: |mutations| &mutations.max_acceleration,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.max_turn_rate,
+        set: |genes, value| genes.max_turn_rate = value,
+        mutation: |mutations| &mutations.max_turn_rate,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.bite_size,
+        set: |genes, value| genes.bite_size = value,
+        mutation: |mutations| &mutations.bite_size,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.3, // a thrifty allele mostly wins
+        get: |genes| genes.hunger_rate,
+        set: |genes, value| genes.hunger_rate = value,
+        mutation: |mutations| &mutations.hunger_rate,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.max_age,
+        set: |genes, value| genes.max_age = value,
+        mutation: |mutations| &mutations.max_age,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.sleep_duration,
+        set: |genes, value| genes.sleep_duration = value,
+        mutation: |mutations| &mutations.sleep_duration,
+    },
+    Locus {
+        kind: LocusKind::Count,
+        dominance: 0.5,
+        get: |genes| genes.max_offspring_count as f32,
+        set: |genes, value| genes.max_offspring_count = value.round().max(0.0) as u32,
+        mutation: |mutations| &mutations.max_offspring_count,
+    },
+    Locus {
+        kind: LocusKind::Switch,
+        dominance: 1.0, // one copy is enough to produce food
+        get: |genes| genes.can_produce_food as u8 as f32,
+        set: |genes, value| genes.can_produce_food = value >= 0.5,
+        mutation: |mutations| &mutations.can_produce_food,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.aggression,
+        set: |genes, value| genes.aggression = value,
+        mutation: |mutations| &mutations.aggression,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.5,
+        get: |genes| genes.attack_range,
+        set: |genes, value| genes.attack_range = value,
+        mutation: |mutations| &mutations.attack_range,
+    },
+    Locus {
+        kind: LocusKind::Trait,
+        dominance: 0.7,
+        get: |genes| genes.chase_speed,
+        set: |genes, value| genes.chase_speed = value,
+        mutation: |mutations| &mutations.chase_speed,
+    },
+];
+
+/// Two alleles for every locus, one strand from each parent.
+/// The `Genes` component is what this genome expresses.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Genome {
+    pub maternal: Genes,
+    pub paternal: Genes,
+}
+
+impl Genome {
+    /// Both strands the same, it breeds true
+    pub fn homozygous(genes: Genes) -> Self {
+        Self {
+            maternal: genes,
+            paternal: genes,
+        }
+    }
+
+    /// Every allele of both strands drawn between the species limits
+    pub fn random_between(
+        min: &Genes,
+        max: &Genes,
+        mutations: &GeneMutations,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let gender = rng.gen_bool(0.5);
+        let mut strands = [*min, *min];
+        for strand in strands.iter_mut() {
+            strand.gender = gender;
+            for locus in LOCI.iter() {
+                let (a, b) = ((locus.get)(min), (locus.get)(max));
+                let (low, high) = (a.min(b), a.max(b));
+                let value = if low < high {
+                    rng.gen_range(low..=high)
+                } else {
+                    low
+                };
+                (locus.set)(strand, locus.clamp(value, mutations));
+            }
+        }
+        Self {
+            maternal: strands[0],
+            paternal: strands[1],
+        }
+    }
+
+    /// The traits it shows, each locus mixes its two alleles by its dominance
+    pub fn express(&self, mutations: &GeneMutations) -> Genes {
+        let mut genes = self.maternal;
+        for locus in LOCI.iter() {
+            let (a, b) = ((locus.get)(&self.maternal), (locus.get)(&self.paternal));
+            let (low, high) = (a.min(b), a.max(b));
+            (locus.set)(&mut genes, low + (high - low) * locus.dominance);
+        }
+        clamp_genes(&genes, mutations)
+    }
+
+    /// One strand to pass on, crossing over between the two strands along the way
+    /// and with the odd mutated allele
+    pub fn gamete(&self, params: &SimulationParams, rng: &mut impl Rng) -> Genes {
+        let mut from_maternal = rng.gen_bool(0.5);
+        let mutations = &params.gene_mutations;
+        let mut gamete = self.maternal;
+        for locus in LOCI.iter() {
+            if rng.gen_bool(params.crossover_rate as f64) {
+                from_maternal = !from_maternal;
+            }
+            let strand = if from_maternal {
+                &self.maternal
+            } else {
+                &self.paternal
+            };
+            let mut value = locus.clamp((locus.get)(strand), mutations);
+            if rng.gen_bool((locus.mutation)(mutations).rate as f64) {
+                value = locus.mutate(value, mutations, rng);
+            }
+            (locus.set)(&mut gamete, value);
+        }
+        gamete
+    }
+
+    /// A child's genome, one gamete from each parent and a random sex
+    pub fn offspring(
+        mother: &Genome,
+        father: &Genome,
+        params: &SimulationParams,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let gender = rng.gen_bool(0.5);
+        let mut maternal = mother.gamete(params, rng);
+        let mut paternal = father.gamete(params, rng);
+        maternal.gender = gender;
+        paternal.gender = gender;
+        Self { maternal, paternal }
+    }
+}
@@ -0,0 +1,145 @@
+use super::components::{
+    Age, Genes, Heading, Herd, LastHerdId, LivingEntity, Position, Predator, SpatialGrid, SpeciesId,
+};
+use crate::resources::params::SimulationParams;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+// a creature as seen by its herd mates, taken before any herd is changed
+struct Member {
+    entity: Entity,
+    pos: Vec2,
+    heading: Vec2,
+    species: u32,
+    is_predator: bool,
+    age: f32,
+    herd_id: Option<u32>,
+}
+
+// union find root, flattening the path on the way
+pub(super) fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+// same species neighbors flock together like boids, how much they care is their sociality.
+// creatures linked through herd mates form one herd, led by its oldest member
+pub fn herd_system(
+    grid: Res<SpatialGrid>,
+    mut query: Query<
+        (
+            Entity,
+            &Position,
+            &Genes,
+            &SpeciesId,
+            &Age,
+            &Heading,
+            &mut Herd,
+            Has<Predator>,
+        ),
+        With<LivingEntity>,
+    >,
+    params: Res<SimulationParams>,
+    mut last_herd_id: ResMut<LastHerdId>,
+) {
+    let members: Vec<Member> = query
+        .iter()
+        .map(
+            |(entity, pos, _, species_id, age, heading, herd, is_predator)| Member {
+                entity,
+                pos: pos.0,
+                heading: heading.0,
+                species: species_id.0,
+                is_predator,
+                age: age.0,
+                herd_id: herd.id,
+            },
+        )
+        .collect();
+    let index: HashMap<Entity, usize> = members
+        .iter()
+        .enumerate()
+        .map(|(i, member)| (member.entity, i))
+        .collect();
+
+    let mut parents: Vec<usize> = (0..members.len()).collect();
+    let mut steerings = vec![Vec2::ZERO; members.len()];
+    for (i, member) in members.iter().enumerate() {
+        let mut mates = 0;
+        let mut center = Vec2::ZERO;
+        let mut heading = Vec2::ZERO;
+        let mut separation = Vec2::ZERO;
+        for (other, mate_pos, dist) in grid.within_radius(member.pos, params.herd_radius) {
+            let Some(&j) = index.get(&other) else {
+                continue;
+            };
+            let mate = &members[j];
+            if j == i || mate.species != member.species || mate.is_predator != member.is_predator {
+                continue;
+            }
+            mates += 1;
+            center += mate_pos;
+            heading += mate.heading;
+            if dist < params.herd_separation_distance {
+                // stronger when closer
+                let strength = 1.0 - dist / params.herd_separation_distance;
+                separation += (member.pos - mate_pos).normalize_or_zero() * strength;
+            }
+            let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+            parents[root_i.max(root_j)] = root_i.min(root_j);
+        }
+        if mates > 0 {
+            let cohesion = (center / mates as f32 - member.pos).normalize_or_zero();
+            let alignment = heading.normalize_or_zero();
+            steerings[i] = cohesion * params.herd_cohesion_weight
+                + alignment * params.herd_alignment_weight
+                + separation * params.herd_separation_weight;
+        }
+    }
+
+    // members and leader of every herd, by the index of its root
+    let mut sizes: HashMap<usize, u32> = HashMap::default();
+    let mut leaders: HashMap<usize, usize> = HashMap::default();
+    for i in 0..members.len() {
+        let root = find_root(&mut parents, i);
+        *sizes.entry(root).or_default() += 1;
+        let leader = leaders.entry(root).or_insert(i);
+        if members[i].age > members[*leader].age {
+            *leader = i;
+        }
+    }
+    // a herd keeps its id as long as its leader stays, a new or split herd gets a new one
+    let mut herd_ids: HashMap<usize, u32> = HashMap::default();
+    let mut taken_ids: HashSet<u32> = HashSet::default();
+    let mut roots: Vec<usize> = leaders.keys().copied().collect();
+    roots.sort_unstable();
+    for root in roots {
+        if sizes[&root] < 2 {
+            continue;
+        }
+        let id = match members[leaders[&root]].herd_id {
+            Some(id) if !taken_ids.contains(&id) => id,
+            _ => {
+                last_herd_id.0 += 1;
+                last_herd_id.0
+            }
+        };
+        taken_ids.insert(id);
+        herd_ids.insert(root, id);
+    }
+
+    for (entity, _, genes, _, _, _, mut herd, _) in query.iter_mut() {
+        let i = index[&entity];
+        let root = find_root(&mut parents, i);
+        let size = sizes[&root];
+        *herd = Herd {
+            id: herd_ids.get(&root).copied(),
+            size,
+            leader: (size > 1).then(|| members[leaders[&root]].entity),
+            steering: steerings[i] * genes.sociality,
+        };
+    }
+}
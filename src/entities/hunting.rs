@@ -0,0 +1,111 @@
+use super::components::{
+    Age, BehaviorState, Food, Genes, Heading, IndividualId, Needs, Perception, Position, Predator,
+    Prey, SimulationClock, SpatialGrid, Velocity, create_corpse,
+};
+use super::steering::{Kinematics, pursue, steer};
+use super::systems::corpse_decay_rate;
+use crate::resources::lineage::LineageLedger;
+use crate::resources::params::SimulationParams;
+use bevy::prelude::*;
+
+pub fn hunt_system(
+    mut commands: Commands,
+    mut predator_query: Query<
+        (
+            &mut Position,
+            &mut Needs,
+            &Genes,
+            &mut Perception,
+            &mut Velocity,
+            &mut Heading,
+            &BehaviorState,
+        ),
+        With<Predator>,
+    >,
+    prey_query: Query<
+        (
+            Entity,
+            &Position,
+            &Velocity,
+            &Genes,
+            &Age,
+            &Needs,
+            Option<&IndividualId>,
+        ),
+        (With<Prey>, Without<Predator>),
+    >,
+    food_query: Query<(), With<Food>>,
+    grid: Res<SpatialGrid>,
+    mut ledger: ResMut<LineageLedger>,
+    clock: Res<SimulationClock>,
+    time: Res<Time>,
+    params: Res<SimulationParams>,
+) {
+    let delta_time = time.delta_seconds();
+    // several predators can chase the same prey, only the first one gets it
+    let mut killed = Vec::new();
+    for (mut pos, mut needs, genes, mut perception, mut velocity, mut heading, behavior_state) in
+        predator_query.iter_mut()
+    {
+        let Some(target) = perception.target_prey else {
+            continue;
+        };
+        if *behavior_state == BehaviorState::Sleep {
+            continue;
+        }
+        let Ok((prey_entity, prey_pos, prey_velocity, prey_genes, prey_age, prey_needs, prey_id)) =
+            prey_query.get(target)
+        else {
+            // already eaten or died on its own
+            perception.target_prey = None;
+            continue;
+        };
+        if killed.contains(&prey_entity) {
+            perception.target_prey = None;
+            continue;
+        }
+
+        let distance = pos.0.distance(prey_pos.0);
+        if distance > genes.attack_range {
+            // runs at where the prey is heading, not where it is
+            let force = pursue(
+                pos.0,
+                velocity.0,
+                prey_pos.0,
+                prey_velocity.0,
+                genes.chase_speed,
+            );
+            velocity.0 = steer(
+                velocity.0,
+                force,
+                Kinematics::new(genes, genes.chase_speed),
+                delta_time,
+            );
+            pos.0 += velocity.0 * delta_time;
+            if velocity.0 != Vec2::ZERO {
+                heading.0 = velocity.0.normalize();
+            }
+            continue;
+        }
+
+        // caught it, eat what it needs and leave the rest behind
+        killed.push(prey_entity);
+        perception.target_prey = None;
+        commands.entity(prey_entity).despawn();
+        if let Some(&prey_id) = prey_id {
+            ledger.record_death(prey_id, clock.0);
+        }
+        let flesh_amount = prey_genes.body_biomass(prey_age.0, prey_needs, &params);
+        let amount_eaten = flesh_amount.min(needs.hunger);
+        needs.hunger -= amount_eaten;
+        needs.energy += amount_eaten / 100.0; // eating = energy gain
+        if flesh_amount > amount_eaten {
+            commands.spawn(create_corpse(
+                prey_pos.0,
+                flesh_amount - amount_eaten,
+                params.corpse_decay_time,
+                corpse_decay_rate(&grid, &food_query, prey_pos.0, &params),
+            ));
+        }
+    }
+}
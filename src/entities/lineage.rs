@@ -0,0 +1,29 @@
+use super::components::{
+    Age, Genes, IndividualId, LivingEntity, Predator, SimulationClock, SpeciesId,
+};
+use crate::resources::lineage::LineageLedger;
+use bevy::prelude::*;
+
+// creatures that weren't born in the simulation, from the scenario, a click or a save
+// without a ledger, start a lineage of their own
+pub fn register_founders_system(
+    mut commands: Commands,
+    mut ledger: ResMut<LineageLedger>,
+    clock: Res<SimulationClock>,
+    query: Query<
+        (Entity, &Genes, &SpeciesId, &Age, Has<Predator>),
+        (With<LivingEntity>, Without<IndividualId>),
+    >,
+) {
+    for (entity, genes, species_id, age, is_predator) in query.iter() {
+        let id = ledger.record_birth(
+            None,
+            None,
+            *species_id,
+            is_predator,
+            *genes,
+            clock.0 - age.0,
+        );
+        commands.entity(entity).insert(id);
+    }
+}
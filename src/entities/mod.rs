@@ -1,4 +1,12 @@
 pub mod components;
-pub mod systems;
+pub mod decision;
+pub mod gameloop;
+pub mod genome;
+pub mod herding;
+pub mod hunting;
+pub mod lineage;
 pub mod perception;
-pub mod gameloop;
\ No newline at end of file
+pub mod plants;
+pub mod speciation;
+pub mod steering;
+pub mod systems;
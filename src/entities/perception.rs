@@ -1,59 +1,51 @@
 use crate::entities::components::LivingEntity;
 
 use super::components::{
-    BehaviorState, Corpse, CorpseState, Food, Genes, Needs, Perception, Position, Predator, Prey,
-    SimulationSpeed, SpatialGrid, SpeciesId, WorldObject,
+    BehaviorState, Corpse, CorpseState, Food, Genes, Heading, Needs, Perception, Position,
+    Predator, Prey, SpatialGrid,
 };
+use super::genome::genetic_distance;
+use crate::resources::params::SimulationParams;
+use crate::resources::rng::{RngStream, SimRng};
 use bevy::prelude::*;
-
-const NEIGHBOR_CELLS: [IVec2; 9] = [
-    IVec2::new(-1, -1),
-    IVec2::new(-1, 0),
-    IVec2::new(-1, 1),
-    IVec2::new(0, -1),
-    IVec2::new(0, 0),
-    IVec2::new(0, 1),
-    IVec2::new(1, -1),
-    IVec2::new(1, 0),
-    IVec2::new(1, 1),
-];
-const NEARBY_AVOIDANCE_DISTANCE: f32 = 5.0;
-const MATE_DETECTION_DISTANCE: f32 = 10.0;
+use rand::Rng;
 
 pub fn perception_scan_system(
     grid: Res<SpatialGrid>,
     mut query: Query<
         (
             Entity,
-            &Transform,
+            &Position,
             &Genes,
+            &Heading,
             &mut Perception,
             &BehaviorState,
             &Needs,
-            &SpeciesId,
+            Has<Predator>,
         ),
-        With<Prey>,
+        With<LivingEntity>,
     >,
-    lookup_query: Query<&Position, With<WorldObject>>,
     food_query: Query<Entity, With<Food>>,
     predator_query: Query<Entity, With<Predator>>,
+    prey_query: Query<Entity, With<Prey>>,
     corpse_query: Query<&CorpseState, With<Corpse>>,
-    needs_query: Query<(&Needs, &Genes, &SpeciesId), With<LivingEntity>>,
+    needs_query: Query<(&Needs, &Genes, Has<Predator>), With<LivingEntity>>,
     time: Res<Time>,
-    simulation_speed: Res<SimulationSpeed>,
+    mut sim_rng: ResMut<SimRng>,
+    params: Res<SimulationParams>,
 ) {
-    let delta_time = time.delta_seconds() * simulation_speed.0;
-    for (entity, transform, genes, mut perception, behavior_state, needs, species_id) in
+    let delta_time = time.delta_seconds();
+    let rng = sim_rng.stream(RngStream::Perception);
+    for (entity, position, genes, heading, mut perception, behavior_state, needs, is_predator) in
         query.iter_mut()
     {
         perception.time_since_last_sense += delta_time;
         perception.time_since_last_target += delta_time;
         let mut skip_sense = false;
         let laziness_threshold = genes.laziness * 10.0;
+        let asleep = *behavior_state == BehaviorState::Sleep;
 
-        if perception.time_since_last_sense < laziness_threshold
-            || *behavior_state == BehaviorState::Sleep
-        {
+        if perception.time_since_last_sense < laziness_threshold || asleep {
             // too lazy or sleeping would not be able to see nearby entities
             // but should know the position of nearby entities so we can avoid them
             skip_sense = true;
@@ -64,9 +56,17 @@ pub fn perception_scan_system(
         // we update neighbors always even if they are lazy or sleeping
         // so we have advantage for other systems to know the position of nearby entities
         perception.neighbors.clear();
+        if asleep {
+            // a sleeper only notices predators right next to it, which wakes it up
+            perception.visible_predators.clear();
+            perception.threats.clear();
+        }
         if !skip_sense {
             perception.target_food = None;
+            perception.target_prey = None;
+            perception.target_corpse = None;
             perception.visible_predators.clear();
+            perception.threats.clear();
             perception.nearby_corpses.clear();
             perception.nearby_mates.clear();
 
@@ -75,91 +75,143 @@ pub fn perception_scan_system(
                 let change_interval = 3.0.lerp(12.0, 1.0 - genes.curiosity);
                 if perception.time_since_last_target > change_interval {
                     perception.time_since_last_target = 0.0;
-                    let angle = rand::random::<f32>() * std::f32::consts::TAU;
+                    let angle = rng.gen_range(0.0..std::f32::consts::TAU);
                     let distance = if needs.sanity < 0.1 {
                         genes.wander_radius
                     } else {
-                        rand::random::<f32>() * genes.wander_radius
+                        rng.gen_range(0.0..1.0) * genes.wander_radius
                     };
-                    perception.target =
-                        Some(transform.translation.truncate() + Vec2::from_angle(angle) * distance);
+                    perception.target = Some(position.0 + Vec2::from_angle(angle) * distance);
                 }
             } else {
                 perception.target = None;
             }
         }
 
-        let pos = transform.translation.truncate();
-        let cell = IVec2::new(
-            (pos.x / grid.cell_size).floor() as i32,
-            (pos.y / grid.cell_size).floor() as i32,
-        );
+        let pos = position.0;
         let mut visible_food: Vec<(Entity, f32)> = Vec::new();
         let mut closest_food_dist: f32 = f32::INFINITY;
         let mut closest_food_entity: Option<Entity> = None;
-        for offset in NEIGHBOR_CELLS {
-            if let Some(entities) = grid.buckets.get(&(cell + offset)) {
-                for &other in entities {
-                    if other == entity {
-                        continue;
-                    }
-                    let Ok(other_pos) = lookup_query.get(other) else {
-                        continue;
-                    };
+        let mut closest_corpse_dist: f32 = f32::INFINITY;
+        // aggressive predators start hunting long before they starve
+        let hunting =
+            is_predator && needs.hunger > params.predator_hunt_hunger * (1.0 - genes.aggression);
+        // carnivores eat corpses whenever they would hunt, prey only when starving
+        let scavenging = hunting || (!is_predator && needs.hunger > params.scavenge_hunger);
 
-                    let dist = pos.distance(other_pos.0);
+        // what is right around it, checked every step
+        let close_radius = params
+            .nearby_avoidance_distance
+            .max(params.mate_detection_distance)
+            .max(params.sleep_wake_distance);
+        for (other, other_pos, dist) in grid.within_radius(pos, close_radius) {
+            if other == entity {
+                continue;
+            }
+            if dist < params.nearby_avoidance_distance {
+                // very close position occupied by something
+                perception.neighbors.push(other_pos);
+            }
+            if dist < params.mate_detection_distance
+                && needs.mate_ready
+                && genes.in_view(heading.0, other_pos - pos, &params)
+            {
+                if let Ok((other_needs, other_genes, other_is_predator)) = needs_query.get(other) {
+                    // only mates with genes close enough to its own
+                    if other_needs.mate_ready
+                        && other_genes.gender != genes.gender
+                        && other_is_predator == is_predator
+                        && genetic_distance(genes, other_genes, &params.gene_mutations)
+                            <= params.mate_max_genetic_distance
+                    {
+                        perception.nearby_mates.push(other);
+                    }
+                }
+            }
+            if asleep
+                && !is_predator
+                && dist < params.sleep_wake_distance
+                && predator_query.get(other).is_ok()
+            {
+                perception.visible_predators.push(other);
+                // as scary as the same predator seen while awake, one that sees less
+                // far than the wake distance still fears what wakes it
+                let range = genes.sight_range(&params).max(params.sleep_wake_distance);
+                let strength = 1.0 - dist / range;
+                perception.threats.push((other_pos, strength));
+            }
+        }
 
-                    if dist < NEARBY_AVOIDANCE_DISTANCE {
-                        // very close position occupied by something
-                        perception.neighbors.push(other_pos.0);
+        // everything it can see or smell, only when it looks around.
+        // corpses are smelled from all around, the rest has to be in its vision cone
+        if !skip_sense {
+            if is_predator {
+                // the closest meal is all a carnivore looks for
+                if scavenging {
+                    perception.target_corpse = grid
+                        .nearest(pos, genes.vision_range, |other, _| {
+                            corpse_query.get(other).is_ok()
+                        })
+                        .map(|(corpse, ..)| corpse);
+                }
+                if hunting {
+                    perception.target_prey = grid
+                        .nearest(pos, genes.sight_range(&params), |other, other_pos| {
+                            prey_query.get(other).is_ok()
+                                && genes.in_view(heading.0, other_pos - pos, &params)
+                        })
+                        .map(|(prey, ..)| prey);
+                }
+            } else {
+                for (other, other_pos, dist) in grid.within_radius(pos, genes.vision_range) {
+                    if other == entity {
+                        continue;
                     }
-                    if dist < MATE_DETECTION_DISTANCE && needs.mate_ready {
-                        if let Ok((other_needs, other_genes, other_species_id)) =
-                            needs_query.get(other)
-                        {
-                            // always choose same species for mating
-                            if other_needs.mate_ready
-                                && other_genes.gender != genes.gender
-                                && other_species_id.0 == species_id.0
-                            {
-                                perception.nearby_mates.push(other);
-                            }
+                    // if other is corpse
+                    if let Ok(corpse_state) = corpse_query.get(other) {
+                        // stench is stronger when closer and weaker when further away
+                        let stench = (params.corpse_decay_time - corpse_state.decay_timer) / dist;
+                        perception.nearby_corpses.push((other_pos, stench));
+                        if scavenging && dist < closest_corpse_dist {
+                            closest_corpse_dist = dist;
+                            perception.target_corpse = Some(other);
                         }
                     }
-                    if !skip_sense {
-                        // if other is corpse
-                        if let Ok(corpse_state) = corpse_query.get(other) {
-                            if dist < genes.vision_range {
-                                // stench is stronger when closer and weaker when further away
-                                let stench = (100.0 - corpse_state.decay_timer) / dist;
-                                perception.nearby_corpses.push((other_pos.0, stench));
+                    if needs.sanity > 0.1 && genes.in_view(heading.0, other_pos - pos, &params) {
+                        // if other is food
+                        if food_query.get(other).is_ok() {
+                            visible_food.push((other, dist));
+                            if dist < closest_food_dist {
+                                closest_food_dist = dist;
+                                closest_food_entity = Some(other);
                             }
                         }
-                        if needs.sanity > 0.1 {
-                            // if other is food
-                            if food_query.get(other).is_ok() && dist < genes.vision_range {
-                                visible_food.push((other, dist));
-                                if dist < closest_food_dist {
-                                    closest_food_dist = dist;
-                                    closest_food_entity = Some(other);
-                                }
-                            }
-                            // if other is predator
-                            if predator_query.get(other).is_ok() && dist < genes.vision_range {
-                                perception.visible_predators.push(other);
-                            }
+                        // if other is predator
+                        if predator_query.get(other).is_ok() {
+                            perception.visible_predators.push(other);
+                            // closer predators are scarier
+                            let strength = 1.0 - dist / genes.sight_range(&params);
+                            perception.threats.push((other_pos, strength));
                         }
                     }
                 }
             }
         }
+        if perception.target_corpse.is_some() {
+            // an easy meal, no need to chase anything
+            perception.target_prey = None;
+        }
+        if perception.target_prey.is_some() || perception.target_corpse.is_some() {
+            // going for a meal, the wander target would only pull it away
+            perception.target = None;
+        }
         if !visible_food.is_empty() {
-            if rand::random::<f32>() < 0.5 {
+            if rng.gen_bool(0.5) {
                 // 50% chance to prefer closer target
                 perception.target_food = closest_food_entity;
             } else {
                 // 50% chance to make a "mistake" and pick a random one
-                let idx = rand::random::<usize>() % visible_food.len();
+                let idx = rng.gen_range(0..visible_food.len());
                 perception.target_food = Some(visible_food[idx].0);
             }
         }
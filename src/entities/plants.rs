@@ -0,0 +1,111 @@
+use super::components::{
+    CarriedSeed, FoodAmount, ForestArea, Needs, Plant, Position, create_plant,
+};
+use crate::resources::params::SimulationParams;
+use crate::resources::rng::{RngStream, SimRng};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::Rng;
+
+// plants regrow and spread seeds inside their forest area until it is full,
+// a plant that is eaten bare dies in `game_loop` like any other food
+pub fn plant_growth_system(
+    mut commands: Commands,
+    mut plant_query: Query<(&Position, &mut FoodAmount, &mut Plant)>,
+    area_query: Query<&ForestArea>,
+    time: Res<Time>,
+    mut sim_rng: ResMut<SimRng>,
+    params: Res<SimulationParams>,
+) {
+    let delta_time = time.delta_seconds();
+    let rng = sim_rng.stream(RngStream::Plants);
+    let areas: HashMap<u32, ForestArea> = area_query.iter().map(|area| (area.id, *area)).collect();
+    let mut plant_counts: HashMap<u32, u32> = HashMap::default();
+    for (_, _, plant) in plant_query.iter() {
+        if let Some(area) = plant.area {
+            *plant_counts.entry(area).or_default() += 1;
+        }
+    }
+
+    for (pos, mut food_amount, mut plant) in plant_query.iter_mut() {
+        food_amount.0 =
+            (food_amount.0 + params.plant_regrow_rate * delta_time).min(plant.max_amount);
+        plant.seed_timer = (plant.seed_timer - delta_time).max(0.0);
+
+        let Some(area) = plant.area.and_then(|id| areas.get(&id)) else {
+            continue;
+        };
+        let mature = food_amount.0 >= plant.max_amount * params.plant_mature_fraction;
+        if !mature || plant.seed_timer > 0.0 {
+            continue;
+        }
+        let count = plant_counts.entry(area.id).or_default();
+        if *count >= area.capacity {
+            continue;
+        }
+        plant.seed_timer = params.plant_seed_interval;
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let distance = rng.gen_range(0.0..params.plant_seed_radius);
+        let seed_pos = pos.0 + Vec2::from_angle(angle) * distance;
+        if !area.contains(seed_pos) {
+            // fell outside the forest and didn't take root
+            continue;
+        }
+        *count += 1;
+        // seedlings start small and grow up to the size of their parent
+        let mut seedling = create_plant(
+            seed_pos,
+            plant.max_amount * params.seedling_fraction,
+            Some(area.id),
+        );
+        seedling.1.max_amount = plant.max_amount;
+        seedling.1.seed_timer = params.plant_seed_interval;
+        commands.spawn(seedling);
+    }
+}
+
+// creatures with the `can_produce_food` gene plant the seeds they carry,
+// which costs energy, so the gene only spreads if the extra food pays off
+pub fn seed_dispersal_system(
+    mut commands: Commands,
+    mut carrier_query: Query<(Entity, &Position, &mut Needs, &mut CarriedSeed)>,
+    plant_query: Query<&Plant>,
+    area_query: Query<&ForestArea>,
+    time: Res<Time>,
+    params: Res<SimulationParams>,
+) {
+    let delta_time = time.delta_seconds();
+    let mut plant_counts: HashMap<u32, u32> = HashMap::default();
+    for plant in plant_query.iter() {
+        if let Some(area) = plant.area {
+            *plant_counts.entry(area).or_default() += 1;
+        }
+    }
+    for (entity, pos, mut needs, mut seed) in carrier_query.iter_mut() {
+        seed.timer -= delta_time;
+        if seed.timer > 0.0 {
+            continue;
+        }
+        commands.entity(entity).remove::<CarriedSeed>();
+        if needs.energy < params.seed_energy_cost {
+            // too tired to plant it, the seed is lost
+            continue;
+        }
+        needs.energy -= params.seed_energy_cost;
+        // planted back inside its forest it can spread further, elsewhere or in a full
+        // forest it only regrows
+        let area = seed
+            .area
+            .and_then(|id| area_query.iter().find(|area| area.id == id))
+            .filter(|area| area.contains(pos.0))
+            .filter(|area| plant_counts.get(&area.id).copied().unwrap_or(0) < area.capacity)
+            .map(|area| area.id);
+        if let Some(id) = area {
+            *plant_counts.entry(id).or_default() += 1;
+        }
+        let mut seedling = create_plant(pos.0, seed.max_amount * params.seedling_fraction, area);
+        seedling.1.max_amount = seed.max_amount;
+        seedling.1.seed_timer = params.plant_seed_interval;
+        commands.spawn(seedling);
+    }
+}
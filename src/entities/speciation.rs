@@ -0,0 +1,219 @@
+use super::components::{EntityColor, Genes, LivingEntity, Predator, SimulationClock, SpeciesId};
+use super::genome::{gene_range, genetic_distance, mean_genes};
+use super::herding::find_root;
+use crate::resources::params::SimulationParams;
+use crate::resources::species::SpeciesRegistry;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+// a living creature as the clustering sees it
+struct Member {
+    entity: Entity,
+    genes: Genes,
+    species: u32,
+    is_predator: bool,
+}
+
+// a species after the split, before merging
+struct Cluster {
+    id: Option<u32>, // none for the parts that split off
+    origin: u32,     // the species its members had
+    is_predator: bool,
+    members: Vec<usize>,
+    mean: Genes,
+}
+
+// every now and then, creatures whose genes drifted apart from the rest of their
+// species found a new one, and species whose genes became alike join again
+pub fn speciation_system(
+    mut creature_query: Query<(Entity, &Genes, &mut SpeciesId, Has<Predator>), With<LivingEntity>>,
+    mut registry: ResMut<SpeciesRegistry>,
+    clock: Res<SimulationClock>,
+    time: Res<Time>,
+    params: Res<SimulationParams>,
+) {
+    // runs whenever the clock passes a multiple of the interval, which a loaded save resumes
+    let interval = params.speciation_interval;
+    if (clock.0 / interval).floor() == ((clock.0 - time.delta_seconds()) / interval).floor() {
+        return;
+    }
+    let mutations = &params.gene_mutations;
+
+    let members: Vec<Member> = creature_query
+        .iter()
+        .map(|(entity, genes, species_id, is_predator)| Member {
+            entity,
+            genes: *genes,
+            species: species_id.0,
+            is_predator,
+        })
+        .collect();
+
+    // members of a species linked through close relatives stay together,
+    // prey and predators never share a species
+    let mut groups: HashMap<(u32, bool), Vec<usize>> = HashMap::default();
+    for (i, member) in members.iter().enumerate() {
+        groups
+            .entry((member.species, member.is_predator))
+            .or_default()
+            .push(i);
+    }
+    let mut parents: Vec<usize> = (0..members.len()).collect();
+    for group in groups.values() {
+        for (k, &i) in group.iter().enumerate() {
+            for &j in group[k + 1..].iter() {
+                let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                if root_i != root_j
+                    && genetic_distance(&members[i].genes, &members[j].genes, mutations)
+                        <= params.speciation_distance
+                {
+                    parents[root_i.max(root_j)] = root_i.min(root_j);
+                }
+            }
+        }
+    }
+    let mut components: HashMap<usize, Vec<usize>> = HashMap::default();
+    for i in 0..members.len() {
+        let root = find_root(&mut parents, i);
+        components.entry(root).or_default().push(i);
+    }
+    let mut components: Vec<Vec<usize>> = components.into_values().collect();
+    // the biggest part of a species keeps its id, the others split off
+    components.sort_by_key(|component| (std::cmp::Reverse(component.len()), component[0]));
+    let mut kept_ids: Vec<u32> = Vec::new();
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for component in components {
+        let first = &members[component[0]];
+        let id = (!kept_ids.contains(&first.species)).then_some(first.species);
+        kept_ids.extend(id);
+        clusters.push(Cluster {
+            id,
+            origin: first.species,
+            is_predator: first.is_predator,
+            mean: mean_genes(component.iter().map(|&i| &members[i].genes)).unwrap(),
+            members: component,
+        });
+    }
+
+    // species that became alike join the bigger one, clusters are sorted biggest first
+    let mut cluster_parents: Vec<usize> = (0..clusters.len()).collect();
+    for i in 0..clusters.len() {
+        for j in i + 1..clusters.len() {
+            if clusters[i].is_predator == clusters[j].is_predator
+                && genetic_distance(&clusters[i].mean, &clusters[j].mean, mutations)
+                    <= params.species_merge_distance
+            {
+                let (root_i, root_j) = (
+                    find_root(&mut cluster_parents, i),
+                    find_root(&mut cluster_parents, j),
+                );
+                cluster_parents[root_i.max(root_j)] = root_i.min(root_j);
+            }
+        }
+    }
+    let mut ranges: HashMap<usize, (Genes, Genes)> = HashMap::default();
+    for (i, cluster) in clusters.iter().enumerate() {
+        let root = find_root(&mut cluster_parents, i);
+        let range = gene_range(cluster.members.iter().map(|&k| &members[k].genes)).unwrap();
+        let range = match ranges.get(&root) {
+            Some((min, max)) => gene_range([min, max, &range.0, &range.1]).unwrap(),
+            None => range,
+        };
+        ranges.insert(root, range);
+    }
+    // a merged group keeps the id of its biggest cluster that has one, even when
+    // a bigger part that split off joins it
+    let mut surviving_ids: HashMap<usize, u32> = HashMap::default();
+    for (i, cluster) in clusters.iter().enumerate() {
+        let root = find_root(&mut cluster_parents, i);
+        if let Some(id) = cluster.id {
+            surviving_ids.entry(root).or_insert(id);
+        }
+    }
+    for i in 0..clusters.len() {
+        let root = find_root(&mut cluster_parents, i);
+        let id = match surviving_ids.get(&root) {
+            Some(&id) => id,
+            None => {
+                // a group without an id is a new species
+                let (genetic_min, genetic_max) = ranges[&root];
+                let origin = clusters[root].origin;
+                let species = registry.register(
+                    genetic_min,
+                    genetic_max,
+                    clusters[root].is_predator,
+                    clock.0,
+                );
+                species.parent = Some(SpeciesId(origin));
+                info!(
+                    "Species {} ({}) split off species {} with {} members",
+                    species.id.0,
+                    species.name,
+                    origin,
+                    clusters[root].members.len()
+                );
+                surviving_ids.insert(root, species.id.0);
+                species.id.0
+            }
+        };
+        if root != i {
+            if let Some(merged_id) = clusters[i].id.filter(|&merged_id| merged_id != id) {
+                info!("Species {} merged into species {}", merged_id, id);
+            }
+            // the surviving species covers the genes of both
+            if let Some(species) = registry.get_mut(SpeciesId(id)) {
+                let (genetic_min, genetic_max) = ranges[&root];
+                let (min, max) = (species.genetic_min, species.genetic_max);
+                (species.genetic_min, species.genetic_max) =
+                    gene_range([&min, &max, &genetic_min, &genetic_max]).unwrap();
+            }
+        }
+        for &k in clusters[i].members.iter() {
+            if members[k].species == id {
+                continue;
+            }
+            if let Ok((_, _, mut species_id, _)) = creature_query.get_mut(members[k].entity) {
+                species_id.0 = id;
+            }
+        }
+    }
+}
+
+// counts the living members of every species, a species without any is extinct
+pub fn species_census_system(
+    mut registry: ResMut<SpeciesRegistry>,
+    clock: Res<SimulationClock>,
+    creature_query: Query<&SpeciesId, With<LivingEntity>>,
+    mut changed_query: Query<(&SpeciesId, &mut EntityColor), Changed<SpeciesId>>,
+) {
+    let mut counts: HashMap<u32, u32> = HashMap::default();
+    for species_id in creature_query.iter() {
+        *counts.entry(species_id.0).or_default() += 1;
+    }
+    for species in registry.iter_mut() {
+        let population = counts.get(&species.id.0).copied().unwrap_or(0);
+        // a species registered this step has no members yet, it isn't extinct,
+        // one that still has none a step later never had any and is
+        let had_time = species.population > 0 || clock.0 > species.founded;
+        if population == 0 && had_time && species.extinct.is_none() {
+            species.extinct = Some(clock.0);
+            if species.population > 0 {
+                info!("Species {} ({}) died out", species.id.0, species.name);
+            } else {
+                info!(
+                    "Species {} ({}) never had members",
+                    species.id.0, species.name
+                );
+            }
+        } else if population > 0 {
+            species.extinct = None;
+        }
+        species.population = population;
+    }
+    // creatures that joined another species take its color
+    for (species_id, mut color) in changed_query.iter_mut() {
+        if let Some(species) = registry.get(*species_id) {
+            color.0 = species.color();
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::components::Genes;
+use bevy::prelude::*;
+use rand::Rng;
+
+// Steering behaviors, each returns a force that nudges the current velocity
+// toward what the behavior wants, so they can be weighted and added up.
+// `steer` then applies the sum within the creature's acceleration and turning limits.
+
+// how far ahead the wander circle sits and how big it is, relative to the speed
+const WANDER_DISTANCE: f32 = 2.0;
+const WANDER_RADIUS: f32 = 1.0;
+const WANDER_JITTER: f32 = 0.5; // radians the wander point can move per call
+
+/// Full speed toward the target
+pub fn seek(pos: Vec2, velocity: Vec2, target: Vec2, max_speed: f32) -> Vec2 {
+    (target - pos).normalize_or_zero() * max_speed - velocity
+}
+
+/// Seek where a moving target will be by the time it gets there
+pub fn pursue(
+    pos: Vec2,
+    velocity: Vec2,
+    target: Vec2,
+    target_velocity: Vec2,
+    max_speed: f32,
+) -> Vec2 {
+    let lookahead = pos.distance(target) / max_speed.max(0.01);
+    seek(
+        pos,
+        velocity,
+        target + target_velocity * lookahead,
+        max_speed,
+    )
+}
+
+/// Like `seek`, but slows down inside `slowing_radius` to stop on the target.
+/// Without a slowing radius it is a plain `seek`.
+pub fn arrive(
+    pos: Vec2,
+    velocity: Vec2,
+    target: Vec2,
+    max_speed: f32,
+    slowing_radius: f32,
+) -> Vec2 {
+    if slowing_radius <= 0.0 {
+        return seek(pos, velocity, target, max_speed);
+    }
+    let offset = target - pos;
+    let distance = offset.length();
+    let speed = max_speed * (distance / slowing_radius).min(1.0);
+    offset.normalize_or_zero() * speed - velocity
+}
+
+/// Full speed away from the threat
+pub fn flee(pos: Vec2, velocity: Vec2, threat: Vec2, max_speed: f32) -> Vec2 {
+    (pos - threat).normalize_or_zero() * max_speed - velocity
+}
+
+/// Meander ahead, a point on a circle in front of it drifts a little every call
+pub fn wander(velocity: Vec2, heading: Vec2, max_speed: f32, rng: &mut impl Rng) -> Vec2 {
+    let heading = if heading == Vec2::ZERO {
+        Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU))
+    } else {
+        heading
+    };
+    let angle = heading.to_angle() + rng.gen_range(-WANDER_JITTER..WANDER_JITTER);
+    let target = heading * WANDER_DISTANCE + Vec2::from_angle(angle) * WANDER_RADIUS;
+    target.normalize_or_zero() * max_speed - velocity
+}
+
+/// Away from everything closer than `radius`, stronger when closer
+pub fn separation(pos: Vec2, neighbors: &[Vec2], radius: f32) -> Vec2 {
+    let mut force = Vec2::ZERO;
+    for &neighbor in neighbors {
+        let distance = pos.distance(neighbor);
+        if distance < radius {
+            force += (pos - neighbor).normalize_or_zero() * (radius - distance) / radius;
+        }
+    }
+    force
+}
+
+/// Movement limits of a body, from its genes
+#[derive(Clone, Copy, Debug)]
+pub struct Kinematics {
+    pub max_speed: f32,
+    pub max_acceleration: f32,
+    pub max_turn_rate: f32, // radians per second
+}
+
+impl Kinematics {
+    pub fn new(genes: &Genes, max_speed: f32) -> Self {
+        Self {
+            max_speed,
+            max_acceleration: genes.max_acceleration,
+            max_turn_rate: genes.max_turn_rate,
+        }
+    }
+}
+
+/// The velocity after applying `force` for `delta_time`. The force is cut to the
+/// acceleration limit, the turn to the turning limit and the result to the speed limit.
+pub fn steer(velocity: Vec2, force: Vec2, limits: Kinematics, delta_time: f32) -> Vec2 {
+    let mut new_velocity = velocity + force.clamp_length_max(limits.max_acceleration) * delta_time;
+    if velocity != Vec2::ZERO && new_velocity != Vec2::ZERO {
+        let max_turn = limits.max_turn_rate * delta_time;
+        let turn = velocity.angle_between(new_velocity);
+        if turn.abs() > max_turn {
+            new_velocity = Vec2::from_angle(max_turn.copysign(turn))
+                .rotate(velocity)
+                .normalize()
+                * new_velocity.length();
+        }
+    }
+    new_velocity.clamp_length_max(limits.max_speed)
+}
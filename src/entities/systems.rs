@@ -1,97 +1,258 @@
 use super::components::{
-    Age, BehaviorState, Corpse, CorpseState, Genes, Needs, Position, Prey, SimulationSpeed,
-    SpatialGrid, Species, SpeciesId, WorldObject, create_corpse, create_food, create_prey,
-};
-use crate::{
-    entities::components::Perception,
-    helpers::util::{WORLD_HEIGHT, WORLD_WIDTH},
+    Age, BehaviorState, Corpse, CorpseState, EntityColor, Food, ForestArea, Genes, IndividualId,
+    LivingEntity, Needs, Perception, Position, Predator, SimulationClock, SpatialGrid, Species,
+    SpeciesId, WorldObject, create_corpse, create_food, create_plant, create_predator, create_prey,
 };
+use super::genome::{Genome, clamp_genes};
+use crate::resources::lineage::LineageLedger;
+use crate::resources::params::{GeneMutations, SimulationParams};
+use crate::resources::rng::{RngStream, SimRng};
+use crate::resources::species::SpeciesRegistry;
+use crate::world::scenario::{ForestSettings, RandomSpeciesSettings, Scenario, SpawnRegion};
 use bevy::{prelude::*, window::PrimaryWindow};
 use noisy_bevy::simplex_noise_2d;
 use rand::Rng;
-const DEFAULT_SANITY_GAIN_RATE: f32 = 0.01;
-const MATE_DETECTION_AGE_THRESHOLD_MIN: f32 = 0.2;
-const MATE_DETECTION_AGE_THRESHOLD_MAX: f32 = 0.8;
-const MATE_READY_SANITY_THRESHOLD: f32 = 0.5;
-const MATE_READY_HUNGER_THRESHOLD: f32 = 90.0;
-const MATE_READY_ENERGY_THRESHOLD: f32 = 0.9;
 
-fn spawn_forest(commands: &mut Commands, forest_count: i32, size: f32) {
-    let mut rng = rand::thread_rng();
+fn random_point(rng: &mut impl Rng, region: SpawnRegion) -> Vec2 {
+    Vec2::new(
+        rng.gen_range(region.min.0..=region.max.0),
+        rng.gen_range(region.min.1..=region.max.1),
+    )
+}
+
+fn spawn_forest(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    settings: &ForestSettings,
+    region: SpawnRegion,
+) {
     // first, choose n random areas
     // then we spawn food in those areas based on noise value
     // this will give us forest like areas
+    let forest_count = rng.gen_range(settings.count.0..=settings.count.1);
+    let size = rng.gen_range(settings.size.0..=settings.size.1);
     let mut areas = Vec::new();
     for _ in 0..forest_count {
-        areas.push(Vec2::new(
-            rng.gen_range(0.0..WORLD_WIDTH),
-            rng.gen_range(0.0..WORLD_HEIGHT),
-        ));
+        areas.push(random_point(rng, region));
     }
     let half_size = size / 2.0;
-    for area in areas {
+    for (id, area) in areas.into_iter().enumerate() {
         let density = rng.gen_range(0.5..1.0);
         let food_count_in_area = (simplex_noise_2d(area) * half_size) + half_size; // might move this count to the area
         let offset = density * half_size;
+        // plants keep seeding inside the area they started in
+        let forest = ForestArea {
+            id: id as u32,
+            center: area,
+            half_size: offset,
+            capacity: rng.gen_range(settings.capacity.0..=settings.capacity.1),
+        };
+        commands.spawn(forest);
         // in every area, spread food randomly
         for _ in 0..food_count_in_area as i32 {
             let pos = Vec2::new(
                 rng.gen_range(area.x - offset..area.x + offset),
                 rng.gen_range(area.y - offset..area.y + offset),
             );
-            commands.spawn(create_food(pos, rng.gen_range(10.0..100.0)));
+            let amount = rng.gen_range(settings.food_amount.0..=settings.food_amount.1);
+            commands.spawn(create_plant(pos, amount, Some(forest.id)));
         }
     }
 }
 
-pub fn setup_entities(mut commands: Commands) {
+fn spawn_creature(
+    commands: &mut Commands,
+    pos: Vec2,
+    species_id: SpeciesId,
+    genome: Genome,
+    predator: bool,
+    registry: &SpeciesRegistry,
+    mutations: &GeneMutations,
+) -> Entity {
+    // the bundle is full, the genome goes next to it
+    let genes = genome.express(mutations);
+    // drawn in the color of its species
+    let color = registry
+        .get(species_id)
+        .map(|species| EntityColor(species.color()));
+    if predator {
+        let mut creature = create_predator(pos, species_id, genes);
+        creature.4 = color.unwrap_or(creature.4);
+        commands.spawn((creature, genome)).id()
+    } else {
+        let mut creature = create_prey(pos, species_id, genes);
+        creature.4 = color.unwrap_or(creature.4);
+        commands.spawn((creature, genome)).id()
+    }
+}
+
+// registers a species with random genes close to each other
+fn register_random_species<'a>(
+    registry: &'a mut SpeciesRegistry,
+    rng: &mut impl Rng,
+    predator: bool,
+    mutations: &GeneMutations,
+    now: f32,
+) -> &'a mut Species {
+    let genes = if predator {
+        Genes::random_predator(rng)
+    } else {
+        Genes::random(rng)
+    };
+    // the random ranges don't know about the configured ones
+    let genetic_min = clamp_genes(&genes, mutations);
+    let genetic_max = genetic_min.random_variation(mutations, rng);
+    registry.register(genetic_min, genetic_max, predator, now)
+}
+
+// registers the generated species and returns how many creatures were spawned
+fn spawn_random_species(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    settings: &RandomSpeciesSettings,
+    region: SpawnRegion,
+    registry: &mut SpeciesRegistry,
+    predator: bool,
+    mutations: &GeneMutations,
+) -> u32 {
+    let species_count = rng.gen_range(settings.count.0..=settings.count.1);
+    let random_species: Vec<Species> = (0..species_count)
+        .map(|_| register_random_species(registry, rng, predator, mutations, 0.0).clone())
+        .collect();
+    for _ in 0..settings.population {
+        let pos = random_point(rng, region);
+        let species = &random_species[rng.gen_range(0..random_species.len())];
+        spawn_creature(
+            commands,
+            pos,
+            species.id,
+            species.random_genome(mutations, rng),
+            predator,
+            registry,
+            mutations,
+        );
+    }
+    settings.population
+}
+
+pub fn setup_entities(
+    mut commands: Commands,
+    mut sim_rng: ResMut<SimRng>,
+    mut registry: ResMut<SpeciesRegistry>,
+    scenario: Res<Scenario>,
+    params: Res<SimulationParams>,
+) {
+    let mutations = &params.gene_mutations;
     // Only spawn default entities if no config was loaded
-    let mut rng = rand::thread_rng();
+    info!("Simulation seed: {}", sim_rng.seed);
+    let rng = sim_rng.stream(RngStream::Setup);
+    let whole_world = SpawnRegion::whole_world(scenario.world_size);
     // spawn area based food
     spawn_forest(
         &mut commands,
-        rng.gen_range(20..30),
-        rng.gen_range(100.0..200.0),
+        rng,
+        &scenario.forests,
+        scenario.forests.region.unwrap_or(whole_world),
     );
 
-    let mut vec_species = Vec::new();
-    let species_count = rng.gen_range(5..10);
-    for i in 0..species_count {
-        let genetic_min = Genes::default();
-        vec_species.push(Species {
-            id: SpeciesId(i as u32),
-            genetic_min: genetic_min.clone(),
-            genetic_max: genetic_min.random_variation(),
-        });
+    let mut population = 0;
+    let mut predator_population = 0;
+    // hand picked species first, they get the lowest ids in the order of the scenario
+    for settings in scenario.species.iter() {
+        let species = registry.register(
+            settings.genetic_min,
+            settings.genetic_max,
+            settings.predator,
+            0.0,
+        );
+        if let Some(name) = &settings.name {
+            species.name = name.clone();
+        }
+        let species = species.clone();
+        let region = settings.region.unwrap_or(whole_world);
+        for _ in 0..settings.population {
+            let pos = random_point(rng, region);
+            let genome = species.random_genome(mutations, rng);
+            spawn_creature(
+                &mut commands,
+                pos,
+                species.id,
+                genome,
+                settings.predator,
+                &registry,
+                mutations,
+            );
+        }
+        if settings.predator {
+            predator_population += settings.population;
+        } else {
+            population += settings.population;
+        }
     }
 
-    for _ in 0..2000 {
-        let pos = Vec2::new(
-            rng.gen_range(0.0..WORLD_WIDTH),
-            rng.gen_range(0.0..WORLD_HEIGHT),
+    if let Some(settings) = &scenario.random_species {
+        let region = settings.region.unwrap_or(whole_world);
+        population += spawn_random_species(
+            &mut commands,
+            rng,
+            settings,
+            region,
+            &mut registry,
+            false,
+            mutations,
+        );
+    }
+    if let Some(settings) = &scenario.random_predators {
+        let region = settings.region.unwrap_or(whole_world);
+        predator_population += spawn_random_species(
+            &mut commands,
+            rng,
+            settings,
+            region,
+            &mut registry,
+            true,
+            mutations,
         );
-        let species = vec_species[rng.gen_range(0..vec_species.len())];
-        commands.spawn(create_prey(pos, species.id, species.random_gene()));
     }
 
-    info!("Spawned foods and prey entities");
+    info!(
+        "Spawned foods, {} prey and {} predator entities of {} species",
+        population,
+        predator_population,
+        registry.iter().count()
+    );
+}
+
+pub fn advance_clock_system(mut clock: ResMut<SimulationClock>, time: Res<Time>) {
+    clock.0 += time.delta_seconds();
 }
 
 pub fn update_grid_system(
     mut grid: ResMut<SpatialGrid>,
-    query: Query<(Entity, &Transform), With<WorldObject>>,
+    query: Query<(Entity, &Position), With<WorldObject>>,
 ) {
     grid.buckets.clear();
-    for (entity, transform) in query.iter() {
-        let pos = transform.translation.truncate();
-        let cell = IVec2::new(
-            (pos.x / grid.cell_size).floor() as i32,
-            (pos.y / grid.cell_size).floor() as i32,
-        );
-        grid.buckets.entry(cell).or_default().push(entity);
+    for (entity, position) in query.iter() {
+        grid.insert(entity, position.0);
     }
 }
 
+// corpses lying among plants rot faster than on bare ground
+pub fn corpse_decay_rate(
+    grid: &SpatialGrid,
+    food_query: &Query<(), With<Food>>,
+    pos: Vec2,
+    params: &SimulationParams,
+) -> f32 {
+    let plants = grid.buckets.get(&grid.cell(pos)).map_or(0, |entities| {
+        entities
+            .iter()
+            .filter(|(entity, _)| food_query.get(*entity).is_ok())
+            .count()
+    });
+    params.corpse_decay_rate * (1.0 + (plants as f32 / params.lush_food_count).min(1.0))
+}
+
 pub fn update_entities(
     mut commands: Commands,
     mut query: Query<
@@ -99,47 +260,100 @@ pub fn update_entities(
             Entity,
             &mut Needs,
             &Genes,
+            &Genome,
             &mut Age,
             &Position,
-            &mut BehaviorState,
-            &mut Transform,
+            &BehaviorState,
             &SpeciesId,
+            &Perception,
+            Has<Predator>,
+            Option<&IndividualId>,
         ),
-        With<Prey>,
+        With<LivingEntity>,
     >,
     mut corpse_query: Query<(Entity, &mut CorpseState, &Position), With<Corpse>>,
+    food_query: Query<(), With<Food>>,
+    grid: Res<SpatialGrid>,
     // needs_query: Query<&Needs, With<LivingEntity>>,
+    registry: Res<SpeciesRegistry>,
+    mut ledger: ResMut<LineageLedger>,
+    clock: Res<SimulationClock>,
     time: Res<Time>,
-    simulation_speed: Res<SimulationSpeed>,
+    mut sim_rng: ResMut<SimRng>,
+    params: Res<SimulationParams>,
 ) {
-    let delta_time = time.delta_seconds() * simulation_speed.0;
-    let mut rng = rand::thread_rng();
+    let delta_time = time.delta_seconds();
+    let rng = sim_rng.stream(RngStream::Lifecycle);
     // update needs and age
-    for (entity, mut needs, genes, mut age, pos, mut behavior_state, mut transform, species_id) in
-        query.iter_mut()
+    for (
+        entity,
+        mut needs,
+        genes,
+        genome,
+        mut age,
+        pos,
+        behavior_state,
+        species_id,
+        perception,
+        is_predator,
+        individual_id,
+    ) in query.iter_mut()
     {
-        let mut sanity_gain = DEFAULT_SANITY_GAIN_RATE;
-        needs.hunger += genes.hunger_rate * delta_time;
-        needs.hunger = needs.hunger.clamp(0.0, 100.0);
-        if needs.hunger > 90.0 {
-            sanity_gain = -0.1; // hungry = sanity decrease
+        let asleep = *behavior_state == BehaviorState::Sleep;
+        let mut sanity_gain = params.sanity_gain_rate;
+        let hunger_rate = if asleep {
+            genes.hunger_rate * params.sleep_hunger_factor
+        } else {
+            genes.hunger_rate
+        };
+        needs.hunger += hunger_rate * delta_time;
+        needs.hunger = needs.hunger.clamp(0.0, params.max_hunger);
+        if needs.hunger > params.hungry_threshold {
+            sanity_gain = -params.hungry_sanity_loss; // hungry = sanity decrease
         }
-        if needs.hunger >= 100.0 {
+        if needs.hunger >= params.max_hunger {
             needs.energy *= 0.1 * delta_time; // max hungry = energy decrease
+        } else if asleep {
+            // less hungry = more energy
+            needs.energy += params.energy_regen_rate
+                * delta_time
+                * ((params.max_hunger - needs.hunger) / params.max_hunger);
+            needs.sleep_timer -= delta_time;
         } else {
-            // TODO: verify this formula
-            needs.energy += 0.1 * delta_time * ((100.0 - needs.hunger) / 100.0); // less hungry = more energy
+            needs.energy -= params.awake_energy_drain * (0.5 + genes.laziness) * delta_time;
         }
         needs.energy = needs.energy.clamp(0.0, 1.0);
+
+        // fear grows with the threats it sees, bold ones are harder to scare
+        let mut threat: f32 = perception
+            .threats
+            .iter()
+            .map(|(_, strength)| strength)
+            .sum();
+        // carnivores and starving prey are not put off by corpses
+        if !is_predator && needs.hunger <= params.scavenge_hunger {
+            // only corpses close enough to be avoided are scary
+            for &(corpse_pos, stench) in perception.nearby_corpses.iter() {
+                let stench_radius = params.corpse_fear_radius * (1.0 + stench);
+                if pos.0.distance(corpse_pos) <= stench_radius {
+                    threat += stench.min(1.0) * params.corpse_fear_weight;
+                }
+            }
+        }
+        needs.fear += threat * (1.0 - genes.boldness) * params.fear_gain_rate * delta_time;
+        needs.fear -= params.fear_decay_rate * delta_time; // calms down over time
+        needs.fear = needs.fear.clamp(0.0, 1.0);
+        sanity_gain -= needs.fear * params.fear_sanity_loss;
+
         age.0 += delta_time;
         needs.sanity += delta_time * sanity_gain;
         needs.sanity = needs.sanity.clamp(0.0, 1.0);
         needs.mate_ready = needs.partner.is_none()
-            && age.0 >= MATE_DETECTION_AGE_THRESHOLD_MIN
-            && age.0 <= MATE_DETECTION_AGE_THRESHOLD_MAX
-            && needs.sanity >= MATE_READY_SANITY_THRESHOLD
-            && needs.hunger < MATE_READY_HUNGER_THRESHOLD
-            && needs.energy >= MATE_READY_ENERGY_THRESHOLD
+            && age.0 >= params.mate_ready_age_min
+            && age.0 <= params.mate_ready_age_max
+            && needs.sanity >= params.mate_ready_sanity_threshold
+            && needs.hunger < params.mate_ready_hunger_threshold
+            && needs.energy >= params.mate_ready_energy_threshold
             && !needs.pregnant
             && needs.pregnancy_timer <= 0.0;
         // if there is a partner, decrease the partner timer
@@ -152,7 +366,7 @@ pub fn update_entities(
         }
         needs.mating_timer -= delta_time;
         needs.mating_timer = needs.mating_timer.clamp(0.0, 1.0);
-        if genes.gender == false {
+        if !genes.gender {
             needs.pregnancy_timer -= delta_time;
             needs.pregnancy_timer = needs.pregnancy_timer.clamp(0.0, 1.0);
         }
@@ -171,20 +385,18 @@ pub fn update_entities(
         //     }
         // }
 
-        transform.translation = pos.0.extend(0.0);
-        if needs.hunger > 50.0 || (needs.hunger < 80.0 && genes.greed > 0.5) {
-            *behavior_state = BehaviorState::SeekFood; // Re-seek new Food
-        } else if genes.laziness > 0.5 {
-            *behavior_state = BehaviorState::Sleep;
-        } else {
-            *behavior_state = BehaviorState::Wander;
-        }
-
         // update age and death
-        if age.0 >= genes.max_age || (needs.hunger >= 100.0 && needs.energy <= 0.0) {
+        if age.0 >= genes.max_age || (needs.hunger >= params.max_hunger && needs.energy <= 0.0) {
             commands.entity(entity).despawn();
-            // TODO: implement corpse creation here and body flesh amount to be used for food amount
-            commands.spawn(create_corpse(pos.0, rng.gen_range(10.0..50.0)));
+            if let Some(&id) = individual_id {
+                ledger.record_death(id, clock.0);
+            }
+            commands.spawn(create_corpse(
+                pos.0,
+                genes.body_biomass(age.0, &needs, &params),
+                params.corpse_decay_time,
+                corpse_decay_rate(&grid, &food_query, pos.0, &params),
+            ));
         } else if needs.pregnant && needs.partner.is_some() {
             // update pregnancy
             if needs.pregnancy_timer <= 0.0 {
@@ -197,15 +409,39 @@ pub fn update_entities(
                 } else {
                     rng.gen_range(1..genes.max_offspring_count)
                 };
-                let father_genes = needs.partner_genes.clone().unwrap();
+                // an old or hand edited save can hold a pregnancy without the father's
+                // genome, then the children only inherit from their mother
+                let father_genome = needs.partner_genome.unwrap_or(*genome);
                 for _ in 0..offspring_count {
-                    let new_genes = genes.mutate(&father_genes);
-                    let mut child = create_prey(pos.0, species_id.clone(), new_genes);
-                    child.10.mother = Some(entity); // set the mother of the child
-                    commands.spawn(child);
+                    let child_genome = Genome::offspring(genome, &father_genome, &params, rng);
+                    let child_id = ledger.record_birth(
+                        individual_id.copied(),
+                        needs.partner_id,
+                        *species_id,
+                        is_predator,
+                        child_genome.express(&params.gene_mutations),
+                        clock.0,
+                    );
+                    let child = spawn_creature(
+                        &mut commands,
+                        pos.0,
+                        *species_id,
+                        child_genome,
+                        is_predator,
+                        &registry,
+                        &params.gene_mutations,
+                    );
+                    commands.entity(child).insert((
+                        Needs {
+                            mother: Some(entity), // set the mother of the child
+                            ..default()
+                        },
+                        child_id,
+                    ));
                 }
                 needs.partner = None;
-                needs.partner_genes = None;
+                needs.partner_genome = None;
+                needs.partner_id = None;
                 // needs.partner_timer = 0.0;
             }
         }
@@ -214,23 +450,26 @@ pub fn update_entities(
         corpse_state.decay_timer -= delta_time * corpse_state.decay_rate;
         if corpse_state.decay_timer <= 0.0 {
             commands.entity(entity).despawn();
-            commands.spawn(create_food(pos.0, corpse_state.flesh_amount));
+            // whatever flesh is left goes back to the soil, nothing is lost or gained
+            if corpse_state.flesh_amount > 0.0 {
+                commands.spawn(create_food(pos.0, corpse_state.flesh_amount));
+            }
         }
     }
 }
 
 fn mouse_to_world(
-    q_camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
-    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_camera: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    q_windows: &Query<&Window, With<PrimaryWindow>>,
 ) -> Option<Vec2> {
     let (camera, camera_transform) = q_camera.single();
     let window = q_windows.single();
 
     if let Some(screen_position) = window.cursor_position() {
         // Convert screen position to world position
-        return camera
+        camera
             .viewport_to_world(camera_transform, screen_position)
-            .map(|ray| ray.origin.truncate()); // For 2D, the origin is on the Z=0 plane
+            .map(|ray| ray.origin.truncate()) // For 2D, the origin is on the Z=0 plane
     } else {
         None
     }
@@ -241,53 +480,71 @@ pub fn handle_input(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
     q_camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut sim_rng: ResMut<SimRng>,
+    mut registry: ResMut<SpeciesRegistry>,
+    clock: Res<SimulationClock>,
+    params: Res<SimulationParams>,
 ) {
-    let mut rng = rand::thread_rng();
+    let rng = sim_rng.stream(RngStream::Input);
+    let mutations = &params.gene_mutations;
     if mouse_button_input.just_pressed(MouseButton::Left) {
-        if let Some(world_position) = mouse_to_world(q_camera, q_windows) {
+        if let Some(world_position) = mouse_to_world(&q_camera, &q_windows) {
             info!("Mouse clicked at world position: {:?}", world_position);
+            // every click brings a new species
+            let species = register_random_species(&mut registry, rng, false, mutations, clock.0);
+            let species = species.clone();
             for _ in 0..10 {
-                commands.spawn(create_prey(
-                    world_position.into(),
-                    SpeciesId(0),
-                    Genes::default(),
-                ));
+                spawn_creature(
+                    &mut commands,
+                    world_position,
+                    species.id,
+                    species.random_genome(mutations, rng),
+                    false,
+                    &registry,
+                    mutations,
+                );
             }
         }
     } else if mouse_button_input.just_pressed(MouseButton::Right) {
-        if let Some(world_position) = mouse_to_world(q_camera, q_windows) {
+        if let Some(world_position) = mouse_to_world(&q_camera, &q_windows) {
             info!(
                 "Mouse right clicked at world position: {:?}",
                 world_position
             );
             let count = rng.gen_range(10..30);
             for _ in 0..count {
-                commands.spawn(create_food(
+                // planted outside any forest area, they regrow but don't spread
+                commands.spawn(create_plant(
                     world_position
                         + Vec2::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0)),
                     rng.gen_range(10.0..100.0),
+                    None,
                 ));
             }
         }
+    } else if mouse_button_input.just_pressed(MouseButton::Middle) {
+        if let Some(world_position) = mouse_to_world(&q_camera, &q_windows) {
+            info!(
+                "Mouse middle clicked at world position: {:?}",
+                world_position
+            );
+            let species = register_random_species(&mut registry, rng, true, mutations, clock.0);
+            let species = species.clone();
+            for _ in 0..3 {
+                spawn_creature(
+                    &mut commands,
+                    world_position,
+                    species.id,
+                    species.random_genome(mutations, rng),
+                    true,
+                    &registry,
+                    mutations,
+                );
+            }
+        }
     }
 }
 
-// pub fn decision_system(
-//     mut query: Query<(&mut Brain, &Needs, &Perception, &Genes)>,
-// ) {
-//     for (mut brain, needs, perception, genes) in query.iter_mut() {
-//         brain.state = if !perception.visible_predators.is_empty() && needs.fear > genes.panic_threshold {
-//             State::Flee
-//         } else if needs.hunger > 0.7 && !perception.visible_food.is_empty() {
-//             State::SeekFood
-//         } else if needs.energy < 0.3 {
-//             State::Sleep
-//         } else {
-//             State::Wander
-//         };
-//     }
-// }
-
 // fn wandering_system(
 //     mut query: Query<(&Genes, &mut Brain, &Transform)>,
 //     time: Res<Time>,
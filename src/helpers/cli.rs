@@ -0,0 +1,68 @@
+/// Options passed on the command line, e.g. `cargo run -- --headless --ticks 10000 --seed 42`
+#[derive(Clone, Debug, Default)]
+pub struct LaunchOptions {
+    pub headless: bool,           // run without a window or renderer
+    pub max_ticks: Option<u64>,   // stop the headless run after this many ticks
+    pub seed: Option<u64>,        // replay a run, a random seed is used if missing
+    pub load: Option<String>,     // start from a save file instead of a generated world
+    pub save: Option<String>,     // write the world to this file when a headless run ends
+    pub lineage: Option<String>,  // write the lineage tree to this file when a headless run ends
+    pub scenario: Option<String>, // initial world description, the built-in default if missing
+    pub params: Option<String>,   // tunable constants, assets/params.ron if missing
+}
+
+impl LaunchOptions {
+    pub fn from_args() -> Self {
+        let mut options = LaunchOptions::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--ticks" => {
+                    options.max_ticks = args.next().and_then(|value| value.parse().ok());
+                    if options.max_ticks.is_none() {
+                        eprintln!("--ticks expects a positive number, running until extinction");
+                    }
+                }
+                "--seed" => {
+                    options.seed = args.next().and_then(|value| value.parse().ok());
+                    if options.seed.is_none() {
+                        eprintln!("--seed expects a number, using a random seed");
+                    }
+                }
+                "--load" => {
+                    options.load = args.next();
+                    if options.load.is_none() {
+                        eprintln!("--load expects a path to a save file");
+                    }
+                }
+                "--save" => {
+                    options.save = args.next();
+                    if options.save.is_none() {
+                        eprintln!("--save expects a path to write the save file to");
+                    }
+                }
+                "--lineage" => {
+                    options.lineage = args.next();
+                    if options.lineage.is_none() {
+                        eprintln!("--lineage expects a path to write the lineage tree to");
+                    }
+                }
+                "--scenario" => {
+                    options.scenario = args.next();
+                    if options.scenario.is_none() {
+                        eprintln!("--scenario expects a path to a scenario file");
+                    }
+                }
+                "--params" => {
+                    options.params = args.next();
+                    if options.params.is_none() {
+                        eprintln!("--params expects a path to a params file");
+                    }
+                }
+                other => eprintln!("Ignoring unknown argument '{}'", other),
+            }
+        }
+        options
+    }
+}
@@ -1 +1,2 @@
-pub mod util;
\ No newline at end of file
+pub mod cli;
+pub mod util;
@@ -1,9 +1,6 @@
 use bevy::prelude::*;
 
-pub const WORLD_WIDTH: f32 = 800.0;
-pub const WORLD_HEIGHT: f32 = 600.0;
-
 pub const GREEN: Color = Color::srgb(0.0, 1.0, 0.0);
 pub const YELLOW: Color = Color::srgb(1.0, 1.0, 0.0);
-// pub const RED: Color = Color::srgb(1.0, 0.0, 0.0);
-pub const GRAY: Color = Color::srgb(0.3, 0.3, 0.3);
\ No newline at end of file
+pub const RED: Color = Color::srgb(1.0, 0.0, 0.0);
+pub const GRAY: Color = Color::srgb(0.3, 0.3, 0.3);
@@ -0,0 +1,13 @@
+//! 2D life simulation built on bevy.
+//!
+//! Add [`plugins::simulation::SimulationPlugin`] to an `App` together with either
+//! [`plugins::headless::HeadlessPlugin`] or the default bevy plugins and
+//! [`plugins::interactive::InteractivePlugin`] to run it.
+
+// bevy systems naturally take many parameters and long query types
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub mod entities;
+pub mod helpers;
+pub mod plugins;
+pub mod resources;
+pub mod world;
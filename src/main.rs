@@ -1,19 +1,54 @@
 use bevy::prelude::*;
-mod plugins;
-mod entities;
-mod world;
-mod helpers;
-use plugins::simulation::SimulationPlugin;
-use plugins::fps::FpsPlugin;
-use helpers::util::{WORLD_WIDTH, WORLD_HEIGHT};
+use life_sim_2d::plugins::simulation::SimulationPlugin;
+use life_sim_2d::plugins::fps::FpsPlugin;
+use life_sim_2d::plugins::headless::HeadlessPlugin;
+use life_sim_2d::plugins::interactive::InteractivePlugin;
+use life_sim_2d::plugins::species_panel::SpeciesPanelPlugin;
+use life_sim_2d::helpers::cli::LaunchOptions;
+use life_sim_2d::resources::params::ParamsSource;
+use life_sim_2d::resources::rng::SimRng;
+use life_sim_2d::world::config::LoadRequest;
+use life_sim_2d::world::scenario::Scenario;
 
-fn main() {
-    App::new()
-        .add_plugins(
+fn main() -> AppExit {
+    let options = LaunchOptions::from_args();
+    let scenario = match &options.scenario {
+        Some(path) => match Scenario::load(path) {
+            Ok(scenario) => scenario,
+            Err(e) => {
+                eprintln!("Failed to load scenario '{}': {}", path, e);
+                return AppExit::from_code(1);
+            }
+        },
+        None => Scenario::default(),
+    };
+    let (world_width, world_height) = scenario.world_size;
+    let mut app = App::new();
+    app.insert_resource(scenario);
+    if let Some(seed) = options.seed {
+        app.insert_resource(SimRng::new(seed));
+    }
+    if let Some(path) = options.params {
+        app.insert_resource(ParamsSource::new(path));
+    }
+    if let Some(path) = options.load {
+        app.insert_resource(LoadRequest {
+            path,
+            at_startup: true,
+        });
+    }
+    if options.headless {
+        app.add_plugins(HeadlessPlugin {
+            max_ticks: options.max_ticks,
+            save_path: options.save,
+            lineage_path: options.lineage,
+        });
+    } else {
+        app.add_plugins(
             DefaultPlugins.set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "2D Life Simulation".into(),
-                    resolution: (WORLD_WIDTH, WORLD_HEIGHT).into(),
+                    resolution: (world_width, world_height).into(),
                     ..default()
                 }),
                 ..default()
@@ -29,14 +64,9 @@ fn main() {
             })
         )
         .add_plugins(FpsPlugin)
-        .add_plugins(SimulationPlugin) // Custom simulation logic
-        .add_systems(Startup, setup_camera)
-        .run();
+        .add_plugins(SpeciesPanelPlugin)
+        .add_plugins(InteractivePlugin); // Window, camera and input
+    }
+    app.add_plugins(SimulationPlugin) // Custom simulation logic
+        .run()
 }
-
-fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle {
-        transform: Transform::from_translation(Vec3::new(WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0, 0.0)), // Center on world
-        ..default()
-    });
-}
\ No newline at end of file
@@ -0,0 +1,130 @@
+use crate::entities::components::{Genes, Herd, LivingEntity, SimulationSpeed};
+use crate::resources::lineage::LineageLedger;
+use crate::resources::species::SpeciesRegistry;
+use crate::world::config::SaveRequest;
+use bevy::app::{AppExit, ScheduleRunnerPlugin};
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::HashSet;
+use std::time::Duration;
+
+const HEADLESS_REPORT_INTERVAL: u64 = 1000;
+
+#[derive(Resource, Default)]
+pub struct HeadlessRun {
+    pub tick: u64,              // fixed simulation steps run so far
+    pub max_ticks: Option<u64>, // None = run until extinction
+    pub last_report: u64,
+    pub save_path: Option<String>,
+    pub lineage_path: Option<String>,
+}
+
+/// Runs the simulation without a window or renderer, as fast as the CPU allows
+pub struct HeadlessPlugin {
+    pub max_ticks: Option<u64>,
+    pub save_path: Option<String>, // where to save the final world, if anywhere
+    pub lineage_path: Option<String>, // where to write the lineage tree, JSON or Newick
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)))
+            .add_plugins(LogPlugin::default())
+            .insert_resource(HeadlessRun {
+                tick: 0,
+                max_ticks: self.max_ticks,
+                last_report: 0,
+                save_path: self.save_path.clone(),
+                lineage_path: self.lineage_path.clone(),
+            })
+            .add_systems(Startup, one_fixed_step_per_frame)
+            .add_systems(FixedFirst, count_fixed_steps)
+            // before `Last`, so a save requested on the final tick is still written
+            // a failed startup load already asked to exit, don't overwrite a save with nothing
+            .add_systems(
+                PostUpdate,
+                headless_progress_system.run_if(not(on_event::<AppExit>())),
+            );
+    }
+}
+
+// every frame advances the simulation clock by one fixed step, no matter how fast the
+// machine is, rounded up so that no frame falls short of a step
+fn one_fixed_step_per_frame(
+    mut commands: Commands,
+    fixed_time: Res<Time<Fixed>>,
+    speed: Res<SimulationSpeed>,
+) {
+    let nanos = fixed_time.timestep().as_nanos() as f64 / speed.0 as f64;
+    commands.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_nanos(
+        nanos.ceil() as u64,
+    )));
+}
+
+// a tick is a fixed step, however many of them a frame runs
+fn count_fixed_steps(mut run: ResMut<HeadlessRun>) {
+    run.tick += 1;
+}
+
+fn headless_progress_system(
+    mut commands: Commands,
+    mut run: ResMut<HeadlessRun>,
+    living_query: Query<(&Genes, &Herd), With<LivingEntity>>,
+    registry: Res<SpeciesRegistry>,
+    ledger: Res<LineageLedger>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let population = living_query.iter().count();
+    if run.tick - run.last_report >= HEADLESS_REPORT_INTERVAL {
+        run.last_report = run.tick - run.tick % HEADLESS_REPORT_INTERVAL;
+        // shows whether the food producing gene spreads or dies out
+        let producers = living_query
+            .iter()
+            .filter(|(genes, _)| genes.can_produce_food)
+            .count();
+        // and whether herding pays off
+        let herds: HashSet<u32> = living_query
+            .iter()
+            .filter_map(|(_, herd)| herd.id)
+            .collect();
+        let sociality = living_query
+            .iter()
+            .map(|(genes, _)| genes.sociality)
+            .sum::<f32>()
+            / population.max(1) as f32;
+        info!(
+            "Tick {}: {} living entities in {} species, {} of them produce food, {} herds, mean sociality {:.2}",
+            run.tick,
+            population,
+            registry.living().count(),
+            producers,
+            herds.len(),
+            sociality
+        );
+    }
+    if population == 0 {
+        info!("All entities died out after {} ticks", run.tick);
+    } else if run.max_ticks.is_some_and(|max_ticks| run.tick >= max_ticks) {
+        info!(
+            "Reached {} ticks with {} living entities",
+            run.tick, population
+        );
+    } else {
+        return;
+    }
+    if let Some(path) = run.save_path.clone() {
+        commands.insert_resource(SaveRequest(path));
+    }
+    if let Some(path) = &run.lineage_path {
+        match ledger.export(path) {
+            Ok(()) => info!(
+                "Wrote the lineage of {} creatures to {}",
+                ledger.iter().count(),
+                path
+            ),
+            Err(e) => error!("Failed to write the lineage to '{}': {}", path, e),
+        }
+    }
+    exit.send(AppExit::Success);
+}
@@ -0,0 +1,137 @@
+use crate::entities::components::{EntityColor, Position};
+use crate::entities::systems::handle_input;
+use crate::resources::lineage::{LINEAGE_PATH, LineageLedger};
+use crate::world::config::{LoadRequest, SAVE_PATH, SaveRequest};
+use crate::world::scenario::Scenario;
+use bevy::prelude::*;
+const CAMERA_SPEED: f32 = 100.;
+
+/// Everything that needs a window: sprites, camera and keyboard/mouse input
+pub struct InteractivePlugin;
+
+impl Plugin for InteractivePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_camera)
+            .add_systems(Update, (attach_sprites, sync_sprite_colors))
+            .add_systems(FixedFirst, store_previous_positions)
+            .add_systems(Update, interpolate_transforms)
+            // input systems
+            .add_systems(
+                Update,
+                (
+                    save_load_on_keypress,
+                    export_lineage_on_keypress,
+                    move_camera,
+                    handle_input,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_camera(mut commands: Commands, scenario: Res<Scenario>) {
+    let (world_width, world_height) = scenario.world_size;
+    commands.spawn(Camera2dBundle {
+        transform: Transform::from_translation(Vec3::new(
+            world_width / 2.0,
+            world_height / 2.0,
+            0.0,
+        )), // Center on world
+        ..default()
+    });
+}
+
+/// Where the entity was before the latest fixed step, used to smooth rendering between steps
+#[derive(Component)]
+struct PreviousPosition(Vec2);
+
+// the simulation spawns entities without any rendering data,
+// so give every new colored entity a sprite here
+fn attach_sprites(
+    mut commands: Commands,
+    query: Query<(Entity, &EntityColor, &Position), Added<EntityColor>>,
+) {
+    for (entity, color, pos) in query.iter() {
+        commands.entity(entity).insert((
+            PreviousPosition(pos.0),
+            Sprite {
+                color: color.0,
+                custom_size: Some(Vec2::new(2.0, 2.0)),
+                ..default()
+            },
+            Handle::<Image>::default(),
+            GlobalTransform::default(),
+            Visibility::default(),
+            InheritedVisibility::default(),
+            ViewVisibility::default(),
+        ));
+    }
+}
+
+// creatures change color when they join another species
+fn sync_sprite_colors(mut query: Query<(&EntityColor, &mut Sprite), Changed<EntityColor>>) {
+    for (color, mut sprite) in query.iter_mut() {
+        sprite.color = color.0;
+    }
+}
+
+fn store_previous_positions(mut query: Query<(&Position, &mut PreviousPosition)>) {
+    for (pos, mut previous) in query.iter_mut() {
+        previous.0 = pos.0;
+    }
+}
+
+// draw entities between their last two simulated positions, so movement looks
+// smooth whether a frame ran zero, one or several fixed steps
+fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&Position, &PreviousPosition, &mut Transform)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (pos, previous, mut transform) in query.iter_mut() {
+        transform.translation = previous.0.lerp(pos.0, alpha).extend(0.0);
+    }
+}
+
+fn save_load_on_keypress(mut commands: Commands, input: Res<ButtonInput<KeyCode>>) {
+    if input.just_pressed(KeyCode::KeyX) {
+        commands.insert_resource(SaveRequest(SAVE_PATH.into()));
+    } else if input.just_pressed(KeyCode::KeyL) {
+        commands.insert_resource(LoadRequest {
+            path: SAVE_PATH.into(),
+            at_startup: false,
+        });
+    }
+}
+
+fn export_lineage_on_keypress(ledger: Res<LineageLedger>, input: Res<ButtonInput<KeyCode>>) {
+    if input.just_pressed(KeyCode::KeyT) {
+        match ledger.export(LINEAGE_PATH) {
+            Ok(()) => info!("Wrote the lineage tree to {}", LINEAGE_PATH),
+            Err(e) => error!("Failed to write the lineage to '{}': {}", LINEAGE_PATH, e),
+        }
+    }
+}
+
+fn move_camera(
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+    input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+) {
+    let mut camera_transform = camera.single_mut();
+    let mut direction = Vec2::ZERO;
+    if input.pressed(KeyCode::KeyW) {
+        direction.y += 1.;
+    }
+    if input.pressed(KeyCode::KeyS) {
+        direction.y -= 1.;
+    }
+    if input.pressed(KeyCode::KeyA) {
+        direction.x -= 1.;
+    }
+    if input.pressed(KeyCode::KeyD) {
+        direction.x += 1.;
+    }
+    let move_delta = direction.normalize_or_zero() * CAMERA_SPEED * time.delta_seconds();
+    camera_transform.translation += move_delta.extend(0.);
+}
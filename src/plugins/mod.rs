@@ -1,2 +1,5 @@
+pub mod fps;
+pub mod headless;
+pub mod interactive;
 pub mod simulation;
-pub mod fps;
\ No newline at end of file
+pub mod species_panel;
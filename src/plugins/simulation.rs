@@ -1,79 +1,100 @@
-use crate::entities::components::{
-    BehaviorState, EntityColor, Food, Needs, Position, Prey, SimulationSpeed, SpatialGrid,
-};
+use crate::entities::components::{LastHerdId, SimulationClock, SimulationSpeed, SpatialGrid};
+use crate::entities::decision::decision_system;
 use crate::entities::gameloop::game_loop;
+use crate::entities::herding::herd_system;
+use crate::entities::hunting::hunt_system;
+use crate::entities::lineage::register_founders_system;
 use crate::entities::perception::perception_scan_system;
-use crate::entities::systems::{handle_input, setup_entities, update_entities, update_grid_system};
-use crate::world::config::save_config;
+use crate::entities::plants::{plant_growth_system, seed_dispersal_system};
+use crate::entities::speciation::{speciation_system, species_census_system};
+use crate::entities::systems::{
+    advance_clock_system, setup_entities, update_entities, update_grid_system,
+};
+use crate::resources::lineage::LineageLedger;
+use crate::resources::params::{ParamsSource, SimulationParams, reload_params_system};
+use crate::resources::rng::SimRng;
+use crate::resources::species::SpeciesRegistry;
+use crate::world::config::{LoadRequest, SaveRequest, load_world_system, save_world_system};
+use crate::world::scenario::Scenario;
 use bevy::prelude::*;
-const CAMERA_SPEED: f32 = 100.;
+use bevy::time::common_conditions::on_real_timer;
+use std::time::Duration;
+
+// simulation steps per second of simulated time, independent of the frame rate
+pub const SIMULATION_TICK_RATE: f64 = 60.0;
 
+/// The simulation itself, without any rendering or input so it can also run headless
 pub struct SimulationPlugin;
 
 impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
-        // app.add_systems(Startup, load_config)
-        app.add_systems(Startup, setup_entities)
-            .insert_resource(SpatialGrid {
-                cell_size: 64.0,
-                ..Default::default()
-            })
-            .insert_resource(SimulationSpeed(2.0))
-            // entity systems
-            .add_systems(
-                Update,
-                (
-                    update_grid_system,
-                    perception_scan_system,
-                    game_loop,
-                    update_entities,
-                )
-                    .chain(),
+        // a world loaded from a save file replaces the generated one
+        app.add_systems(
+            Startup,
+            (
+                reload_params_system,
+                setup_entities.run_if(not(resource_exists::<LoadRequest>)),
             )
-            // input systems
-            .add_systems(
-                Update,
-                (save_on_keypress, move_camera, handle_input).chain(),
-            );
-    }
-}
-
-fn save_on_keypress(
-    input: Res<ButtonInput<KeyCode>>,
-    query: Query<(
-        &Position,
-        Option<&Food>,
-        Option<&Prey>,
-        &EntityColor,
-        Option<&Needs>,
-        Option<&BehaviorState>,
-    )>,
-) {
-    if input.just_pressed(KeyCode::KeyX) {
-        save_config(query);
-        info!("Saved simulation state to assets/save.ron");
+                .chain(),
+        )
+        .insert_resource(SpatialGrid {
+            cell_size: 64.0,
+            ..Default::default()
+        })
+        .insert_resource(SimulationSpeed(2.0))
+        .init_resource::<SimulationClock>()
+        .init_resource::<LastHerdId>()
+        .init_resource::<SpeciesRegistry>()
+        .init_resource::<LineageLedger>()
+        .insert_resource(Time::<Fixed>::from_hz(SIMULATION_TICK_RATE))
+        // keeps a seed inserted before the plugin, e.g. from --seed
+        .init_resource::<SimRng>()
+        // keeps a scenario inserted before the plugin, e.g. from --scenario
+        .init_resource::<Scenario>()
+        // keeps a params file chosen before the plugin, e.g. from --params
+        .init_resource::<ParamsSource>()
+        .init_resource::<SimulationParams>()
+        // picks up edits to the params file while running
+        .add_systems(
+            Update,
+            reload_params_system.run_if(on_real_timer(Duration::from_secs(1))),
+        )
+        .add_systems(
+            First,
+            apply_simulation_speed.run_if(resource_changed::<SimulationSpeed>),
+        )
+        .add_systems(
+            PreUpdate,
+            load_world_system.run_if(resource_exists::<LoadRequest>),
+        )
+        .add_systems(
+            Last,
+            save_world_system.run_if(resource_exists::<SaveRequest>),
+        )
+        // entity systems, every step sees the same delta time
+        .add_systems(
+            FixedUpdate,
+            (
+                advance_clock_system,
+                register_founders_system,
+                update_grid_system,
+                perception_scan_system,
+                herd_system,
+                decision_system,
+                game_loop,
+                hunt_system,
+                update_entities,
+                plant_growth_system,
+                seed_dispersal_system,
+                speciation_system,
+                species_census_system,
+            )
+                .chain(),
+        );
     }
 }
 
-fn move_camera(
-    mut camera: Query<&mut Transform, With<Camera2d>>,
-    input: Res<ButtonInput<KeyCode>>,
-    time: Res<Time>,
-) {
-    let mut camera_transform = camera.single_mut();
-    let mut direction = Vec2::ZERO;
-    if input.pressed(KeyCode::KeyW) {
-        direction.y += 1.;
-    }
-    if input.pressed(KeyCode::KeyS) {
-        direction.y -= 1.;
-    }
-    if input.pressed(KeyCode::KeyA) {
-        direction.x -= 1.;
-    }
-    if input.pressed(KeyCode::KeyD) {
-        direction.x += 1.;
-    }
-    let move_delta = direction.normalize_or_zero() * CAMERA_SPEED * time.delta_seconds();
-    camera_transform.translation += move_delta.extend(0.);
+// a faster simulation runs more fixed steps per frame instead of longer ones
+fn apply_simulation_speed(speed: Res<SimulationSpeed>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(speed.0);
 }
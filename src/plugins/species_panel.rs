@@ -0,0 +1,104 @@
+use crate::resources::species::SpeciesRegistry;
+use bevy::prelude::*;
+
+const MAX_LISTED_SPECIES: usize = 12;
+
+/// Marker to find the container entity so we can show/hide the species list
+#[derive(Component)]
+struct SpeciesPanelRoot;
+
+/// Marker to find the text entity so we can update it
+#[derive(Component)]
+struct SpeciesPanelText;
+
+fn setup_species_panel(mut commands: Commands) {
+    let root = commands
+        .spawn((
+            SpeciesPanelRoot,
+            NodeBundle {
+                background_color: BackgroundColor(Color::BLACK.with_alpha(0.5)),
+                z_index: ZIndex::Global(i32::MAX),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    // below the FPS counter
+                    right: Val::Percent(1.),
+                    top: Val::Percent(5.),
+                    bottom: Val::Auto,
+                    left: Val::Auto,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .id();
+    let text = commands
+        .spawn((SpeciesPanelText, TextBundle::default()))
+        .id();
+    commands.entity(root).push_children(&[text]);
+}
+
+// one line per living species, biggest first, in the color of its creatures
+fn species_panel_update_system(
+    registry: Res<SpeciesRegistry>,
+    mut query: Query<&mut Text, With<SpeciesPanelText>>,
+) {
+    if !registry.is_changed() {
+        return;
+    }
+    let mut living: Vec<_> = registry.living().collect();
+    living.sort_by_key(|species| (std::cmp::Reverse(species.population), species.id.0));
+    let style = |color| TextStyle {
+        font_size: 14.0,
+        color,
+        ..default()
+    };
+    let mut sections = vec![TextSection::new(
+        format!("Species: {}\n", living.len()),
+        style(Color::WHITE),
+    )];
+    for species in living.iter().take(MAX_LISTED_SPECIES) {
+        sections.push(TextSection::new(
+            format!(
+                "{} #{}: {}\n",
+                species.name, species.id.0, species.population
+            ),
+            style(species.color()),
+        ));
+    }
+    if living.len() > MAX_LISTED_SPECIES {
+        sections.push(TextSection::new(
+            format!("and {} more", living.len() - MAX_LISTED_SPECIES),
+            style(Color::WHITE),
+        ));
+    }
+    for mut text in &mut query {
+        text.sections = sections.clone();
+    }
+}
+
+/// Toggle the species list when pressing F11
+fn species_panel_showhide(
+    mut q: Query<&mut Visibility, With<SpeciesPanelRoot>>,
+    kbd: Res<ButtonInput<KeyCode>>,
+) {
+    if kbd.just_pressed(KeyCode::F11) {
+        let mut vis = q.single_mut();
+        *vis = match *vis {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Lists the living species with their populations
+pub struct SpeciesPanelPlugin;
+
+impl Plugin for SpeciesPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_species_panel).add_systems(
+            Update,
+            (species_panel_update_system, species_panel_showhide),
+        );
+    }
+}
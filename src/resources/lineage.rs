@@ -0,0 +1,201 @@
+use crate::entities::components::{Genes, IndividualId, SpeciesId};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Where the lineage tree is written when exported from the window
+pub const LINEAGE_PATH: &str = "assets/lineage.nwk";
+
+/// What the ledger knows about one creature, living or dead
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LineageRecord {
+    pub id: IndividualId,
+    pub mother: Option<IndividualId>, // none for founders
+    pub father: Option<IndividualId>,
+    pub generation: u32, // founders are 0, children one more than their older parent
+    pub species: SpeciesId, // at birth, it may join another one later
+    pub predator: bool,
+    pub genes: Genes, // as expressed at birth
+    pub born: f32,    // on the simulation clock
+    pub died: Option<f32>,
+}
+
+// a record with its children, how the JSON tree is nested
+#[derive(Serialize)]
+struct LineageNode<'a> {
+    #[serde(flatten)]
+    record: &'a LineageRecord,
+    children: Vec<LineageNode<'a>>,
+}
+
+/// Every creature that ever lived with its parents, so lineages can be traced after death
+#[derive(Resource, Default, Clone, Debug)]
+pub struct LineageLedger {
+    records: Vec<LineageRecord>, // sorted by id
+}
+
+impl LineageLedger {
+    /// The records of a save file
+    pub fn from_records(mut records: Vec<LineageRecord>) -> Self {
+        records.sort_by_key(|record| record.id.0);
+        Self { records }
+    }
+
+    /// Adds a creature with the next free id, founders have no parents
+    pub fn record_birth(
+        &mut self,
+        mother: Option<IndividualId>,
+        father: Option<IndividualId>,
+        species: SpeciesId,
+        predator: bool,
+        genes: Genes,
+        born: f32,
+    ) -> IndividualId {
+        let id = IndividualId(self.records.last().map_or(0, |record| record.id.0 + 1));
+        let generation = [mother, father]
+            .into_iter()
+            .flatten()
+            .filter_map(|parent| self.get(parent))
+            .map(|parent| parent.generation + 1)
+            .max()
+            .unwrap_or(0);
+        self.records.push(LineageRecord {
+            id,
+            mother,
+            father,
+            generation,
+            species,
+            predator,
+            genes,
+            born,
+            died: None,
+        });
+        id
+    }
+
+    pub fn record_death(&mut self, id: IndividualId, died: f32) {
+        if let Some(record) = self.get_mut(id) {
+            record.died.get_or_insert(died);
+        }
+    }
+
+    pub fn get(&self, id: IndividualId) -> Option<&LineageRecord> {
+        let index = self
+            .records
+            .binary_search_by_key(&id.0, |record| record.id.0)
+            .ok()?;
+        Some(&self.records[index])
+    }
+
+    fn get_mut(&mut self, id: IndividualId) -> Option<&mut LineageRecord> {
+        let index = self
+            .records
+            .binary_search_by_key(&id.0, |record| record.id.0)
+            .ok()?;
+        Some(&mut self.records[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LineageRecord> {
+        self.records.iter()
+    }
+
+    // the tree follows the maternal line, a child hangs below its mother and
+    // creatures without a known mother are the roots
+    fn family_tree(&self) -> (Vec<usize>, HashMap<u64, Vec<usize>>) {
+        let mut roots = Vec::new();
+        let mut children: HashMap<u64, Vec<usize>> = HashMap::default();
+        for (index, record) in self.records.iter().enumerate() {
+            match record.mother.filter(|&mother| self.get(mother).is_some()) {
+                Some(mother) => children.entry(mother.0).or_default().push(index),
+                None => roots.push(index),
+            }
+        }
+        (roots, children)
+    }
+
+    /// The maternal family tree in Newick format. Branch lengths are the time between the
+    /// births of mother and child, species, generation, father and death are NHX comments
+    pub fn to_newick(&self) -> String {
+        let (roots, children) = self.family_tree();
+        let mut newick = String::from("(");
+        for (i, &root) in roots.iter().enumerate() {
+            if i > 0 {
+                newick.push(',');
+            }
+            self.write_newick(&mut newick, root, self.records[root].born, &children);
+        }
+        newick.push_str(");");
+        newick
+    }
+
+    fn write_newick(
+        &self,
+        newick: &mut String,
+        index: usize,
+        mother_born: f32,
+        children: &HashMap<u64, Vec<usize>>,
+    ) {
+        let record = &self.records[index];
+        if let Some(children_of) = children.get(&record.id.0) {
+            newick.push('(');
+            for (i, &child) in children_of.iter().enumerate() {
+                if i > 0 {
+                    newick.push(',');
+                }
+                self.write_newick(newick, child, record.born, children);
+            }
+            newick.push(')');
+        }
+        newick.push_str(&format!(
+            "i{}:{}[&&NHX:species={}:generation={}",
+            record.id.0,
+            record.born - mother_born,
+            record.species.0,
+            record.generation
+        ));
+        if let Some(father) = record.father {
+            newick.push_str(&format!(":father={}", father.0));
+        }
+        if let Some(died) = record.died {
+            newick.push_str(&format!(":died={}", died));
+        }
+        newick.push(']');
+    }
+
+    fn node<'a>(&'a self, index: usize, children: &HashMap<u64, Vec<usize>>) -> LineageNode<'a> {
+        let record = &self.records[index];
+        LineageNode {
+            record,
+            children: children
+                .get(&record.id.0)
+                .map(|children_of| {
+                    children_of
+                        .iter()
+                        .map(|&child| self.node(child, children))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The same maternal family tree as nested JSON objects, each with its whole record
+    pub fn to_json(&self) -> Result<String, String> {
+        let (roots, children) = self.family_tree();
+        let nodes: Vec<LineageNode> = roots
+            .into_iter()
+            .map(|root| self.node(root, &children))
+            .collect();
+        serde_json::to_string_pretty(&nodes).map_err(|e| e.to_string())
+    }
+
+    /// Writes the tree to `path`, as JSON for a `.json` file and as Newick otherwise
+    pub fn export(&self, path: &str) -> Result<(), String> {
+        let tree = if path.ends_with(".json") {
+            self.to_json()?
+        } else {
+            self.to_newick()
+        };
+        fs::write(path, tree).map_err(|e| e.to_string())
+    }
+}
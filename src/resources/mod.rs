@@ -0,0 +1,4 @@
+pub mod lineage;
+pub mod params;
+pub mod rng;
+pub mod species;
@@ -0,0 +1,417 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::SystemTime;
+
+pub const PARAMS_PATH: &str = "assets/params.ron";
+
+/// Tunable numbers behind the entity behavior, see assets/params.ron.
+/// Missing fields keep their default, so a params file only needs what it changes.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SimulationParams {
+    // needs
+    pub sanity_gain_rate: f32, // sanity regained per second when not starving
+    pub hungry_sanity_loss: f32, // sanity lost per second above `hungry_threshold`
+    // an average eater wants food as much as anything else at this hunger, greedy ones earlier
+    pub seek_food_hunger: f32,
+    pub hungry_threshold: f32, // sanity starts to drop above this hunger
+    pub max_hunger: f32,       // starving, energy drains and it may die
+
+    // sleep
+    pub energy_regen_rate: f32, // energy regained per second while asleep and not hungry
+    pub awake_energy_drain: f32, // energy lost per second while awake, lazy ones tire faster
+    pub sleep_hunger_factor: f32, // hunger grows this much slower while asleep
+    pub sleep_wake_distance: f32, // a predator closer than this wakes a sleeper up
+
+    // decisions
+    pub seek_food_steepness: f32, // how sharply the wish for food rises around the seek food hunger
+    pub behavior_hysteresis: f32, // score lead a new behavior needs over the current one
+
+    // vision
+    pub wide_view_range_loss: f32, // share of its vision range a full circle view costs
+    pub peripheral_vision_radius: f32, // noticed from any side when closer than this
+
+    // mating
+    pub mate_ready_age_min: f32,
+    pub mate_ready_age_max: f32,
+    pub mate_ready_sanity_threshold: f32,
+    pub mate_ready_hunger_threshold: f32,
+    pub mate_ready_energy_threshold: f32,
+    pub mate_detection_distance: f32,
+    pub mate_max_genetic_distance: f32, // only mates with genes at most this different
+
+    // inheritance
+    pub crossover_rate: f32, // chance a gamete switches strands at each locus
+    pub gene_mutations: GeneMutations, // valid range and mutation of every gene
+
+    // speciation
+    pub speciation_interval: f32, // seconds between two checks for splitting or merging species
+    pub speciation_distance: f32, // relatives closer than this stay in one species
+    pub species_merge_distance: f32, // species whose average genes are closer than this merge
+
+    // movement
+    pub nearby_avoidance_distance: f32, // closer entities are pushed away
+    pub collision_radius: f32,
+    pub collision_repulsion_strength: f32,
+    pub corpse_fear_radius: f32,
+    pub arrive_slowing_radius: f32, // slows down this close to where it is going
+
+    // fear
+    pub fear_gain_rate: f32, // fear gained per second from a threat of strength 1
+    pub fear_decay_rate: f32, // fear lost per second, threats or not
+    pub fear_sanity_loss: f32, // sanity lost per second at full fear
+    pub corpse_fear_weight: f32, // how scary a strongly smelling corpse is next to a predator
+
+    // herding
+    pub herd_radius: f32, // same species closer than this are herd mates
+    pub herd_separation_distance: f32, // herd mates closer than this are pushed apart
+    pub herd_cohesion_weight: f32, // pull toward the center of its herd mates
+    pub herd_alignment_weight: f32, // pull toward the heading of its herd mates
+    pub herd_separation_weight: f32, // push away from crowding herd mates
+
+    // hunting
+    // predators hunt above this hunger, scaled down by their aggression
+    pub predator_hunt_hunger: f32,
+
+    // scavenging
+    pub scavenge_hunger: f32, // prey overcome their disgust of corpses above this hunger
+    pub rotten_freshness: f32, // flesh with less freshness left than this is rotten
+    pub rotten_sanity_loss: f32, // sanity lost per unit of rotten flesh eaten
+
+    // plants
+    pub plant_regrow_rate: f32,     // food a plant regrows per second
+    pub plant_mature_fraction: f32, // share of its max amount a plant needs to spread seeds
+    pub plant_seed_interval: f32,   // seconds between two seeds of the same plant
+    pub plant_seed_radius: f32,     // how far from the plant a seed can land
+    pub seedling_fraction: f32,     // share of the parent's max amount a seedling starts with
+    pub seed_carry_time: f32,       // seconds a food producer carries a seed before planting it
+    pub seed_energy_cost: f32,      // energy it costs to plant a seed
+
+    // corpses
+    pub corpse_decay_time: f32, // how long a corpse lasts before it turns into food
+    pub corpse_decay_rate: f32, // decay speed of a corpse lying on bare ground
+    pub lush_food_count: f32,   // plants around a corpse at which it rots twice as fast
+    pub biomass_per_size: f32,  // flesh of a full grown, well fed body per unit of bite size
+    pub adult_age: f32,         // fraction of its max age at which a body is full grown
+}
+
+impl Default for SimulationParams {
+    fn default() -> Self {
+        Self {
+            sanity_gain_rate: 0.01,
+            hungry_sanity_loss: 0.1,
+            seek_food_hunger: 50.0,
+            hungry_threshold: 90.0,
+            max_hunger: 100.0,
+            energy_regen_rate: 0.3,
+            awake_energy_drain: 0.01,
+            sleep_hunger_factor: 0.5,
+            sleep_wake_distance: 20.0,
+            seek_food_steepness: 10.0,
+            behavior_hysteresis: 0.15,
+            wide_view_range_loss: 0.5,
+            peripheral_vision_radius: 8.0,
+            mate_ready_age_min: 0.2,
+            mate_ready_age_max: 0.8,
+            mate_ready_sanity_threshold: 0.5,
+            mate_ready_hunger_threshold: 90.0,
+            mate_ready_energy_threshold: 0.9,
+            mate_detection_distance: 10.0,
+            mate_max_genetic_distance: 0.08,
+            crossover_rate: 0.1,
+            gene_mutations: GeneMutations::default(),
+            speciation_interval: 10.0,
+            speciation_distance: 0.05,
+            species_merge_distance: 0.02,
+            nearby_avoidance_distance: 5.0,
+            collision_radius: 4.0,
+            collision_repulsion_strength: 50.0,
+            corpse_fear_radius: 10.0,
+            arrive_slowing_radius: 4.0,
+            fear_gain_rate: 1.0,
+            fear_decay_rate: 0.1,
+            fear_sanity_loss: 0.05,
+            corpse_fear_weight: 0.2,
+            herd_radius: 30.0,
+            herd_separation_distance: 8.0,
+            herd_cohesion_weight: 1.0,
+            herd_alignment_weight: 1.0,
+            herd_separation_weight: 1.5,
+            predator_hunt_hunger: 40.0,
+            scavenge_hunger: 85.0,
+            rotten_freshness: 0.3,
+            rotten_sanity_loss: 0.02,
+            plant_regrow_rate: 0.5,
+            plant_mature_fraction: 0.9,
+            plant_seed_interval: 20.0,
+            plant_seed_radius: 15.0,
+            seedling_fraction: 0.2,
+            seed_carry_time: 5.0,
+            seed_energy_cost: 0.05,
+            corpse_decay_time: 100.0,
+            corpse_decay_rate: 1.0,
+            lush_food_count: 20.0,
+            biomass_per_size: 5.0,
+            adult_age: 0.2,
+        }
+    }
+}
+
+impl SimulationParams {
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.crossover_rate) {
+            return Err("crossover_rate must be between 0 and 1".into());
+        }
+        // divided by, or the size of a neighborhood to search
+        for (name, value) in [
+            ("max_hunger", self.max_hunger),
+            ("sleep_wake_distance", self.sleep_wake_distance),
+            ("peripheral_vision_radius", self.peripheral_vision_radius),
+            ("mate_detection_distance", self.mate_detection_distance),
+            ("speciation_interval", self.speciation_interval),
+            ("nearby_avoidance_distance", self.nearby_avoidance_distance),
+            ("collision_radius", self.collision_radius),
+            ("corpse_fear_radius", self.corpse_fear_radius),
+            ("arrive_slowing_radius", self.arrive_slowing_radius),
+            ("herd_radius", self.herd_radius),
+            ("herd_separation_distance", self.herd_separation_distance),
+            ("plant_seed_radius", self.plant_seed_radius),
+            ("corpse_decay_time", self.corpse_decay_time),
+            ("lush_food_count", self.lush_food_count),
+            ("adult_age", self.adult_age),
+        ] {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(format!("{} must be a positive number", name));
+            }
+        }
+        self.gene_mutations.validate()
+    }
+}
+
+/// How far a mutation moves an allele
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub enum MutationStep {
+    Gaussian(f32), // a normally distributed change with this standard deviation
+    Uniform(f32),  // any change up to this much either way
+    Creep(f32),    // exactly this much up or down
+}
+
+/// Where a gene has a meaning and how its alleles mutate.
+/// On or off genes flip when they mutate, whatever their step.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct GeneMutation {
+    pub min: f32,
+    pub max: f32,
+    pub rate: f32, // chance an inherited allele mutates
+    pub step: MutationStep,
+}
+
+impl GeneMutation {
+    const fn new(min: f32, max: f32, rate: f32, step: MutationStep) -> Self {
+        Self {
+            min,
+            max,
+            rate,
+            step,
+        }
+    }
+
+    /// The value moved into the valid range, a broken one falls back to the minimum
+    pub fn clamp(&self, value: f32) -> f32 {
+        if value.is_nan() {
+            self.min
+        } else {
+            value.clamp(self.min, self.max)
+        }
+    }
+
+    fn validate(&self, gene: &str) -> Result<(), String> {
+        if !(self.min.is_finite() && self.max.is_finite()) {
+            return Err(format!(
+                "gene_mutations.{} min and max must be finite numbers",
+                gene
+            ));
+        }
+        if self.min > self.max {
+            return Err(format!(
+                "gene_mutations.{} has min {} above max {}",
+                gene, self.min, self.max
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.rate) {
+            return Err(format!(
+                "gene_mutations.{} rate must be between 0 and 1",
+                gene
+            ));
+        }
+        let (MutationStep::Gaussian(size)
+        | MutationStep::Uniform(size)
+        | MutationStep::Creep(size)) = self.step;
+        if !(size >= 0.0 && size.is_finite()) {
+            return Err(format!(
+                "gene_mutations.{} step must be a positive number",
+                gene
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One entry per gene, missing ones keep their default
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GeneMutations {
+    pub sociality: GeneMutation,
+    pub curiosity: GeneMutation,
+    pub boldness: GeneMutation,
+    pub greed: GeneMutation,
+    pub laziness: GeneMutation,
+    pub panic_threshold: GeneMutation,
+    pub vision_range: GeneMutation,
+    pub field_of_view: GeneMutation,
+    pub wander_radius: GeneMutation,
+    pub max_speed: GeneMutation,
+    pub max_acceleration: GeneMutation,
+    pub max_turn_rate: GeneMutation,
+    pub bite_size: GeneMutation,
+    pub hunger_rate: GeneMutation,
+    pub max_age: GeneMutation,
+    pub sleep_duration: GeneMutation,
+    pub max_offspring_count: GeneMutation,
+    pub can_produce_food: GeneMutation,
+    pub aggression: GeneMutation,
+    pub attack_range: GeneMutation,
+    pub chase_speed: GeneMutation,
+}
+
+impl GeneMutations {
+    fn validate(&self) -> Result<(), String> {
+        for (gene, mutation) in [
+            ("sociality", &self.sociality),
+            ("curiosity", &self.curiosity),
+            ("boldness", &self.boldness),
+            ("greed", &self.greed),
+            ("laziness", &self.laziness),
+            ("panic_threshold", &self.panic_threshold),
+            ("vision_range", &self.vision_range),
+            ("field_of_view", &self.field_of_view),
+            ("wander_radius", &self.wander_radius),
+            ("max_speed", &self.max_speed),
+            ("max_acceleration", &self.max_acceleration),
+            ("max_turn_rate", &self.max_turn_rate),
+            ("bite_size", &self.bite_size),
+            ("hunger_rate", &self.hunger_rate),
+            ("max_age", &self.max_age),
+            ("sleep_duration", &self.sleep_duration),
+            ("max_offspring_count", &self.max_offspring_count),
+            ("can_produce_food", &self.can_produce_food),
+            ("aggression", &self.aggression),
+            ("attack_range", &self.attack_range),
+            ("chase_speed", &self.chase_speed),
+        ] {
+            mutation.validate(gene)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for GeneMutations {
+    fn default() -> Self {
+        use MutationStep::*;
+        let personality = GeneMutation::new(0.0, 1.0, 0.02, Gaussian(0.05));
+        Self {
+            sociality: personality,
+            curiosity: personality,
+            boldness: personality,
+            greed: personality,
+            laziness: personality,
+            panic_threshold: GeneMutation::new(0.05, 1.0, 0.02, Gaussian(0.05)),
+            vision_range: GeneMutation::new(10.0, 1000.0, 0.02, Gaussian(20.0)),
+            field_of_view: GeneMutation::new(0.5, std::f32::consts::TAU, 0.02, Gaussian(0.2)),
+            wander_radius: GeneMutation::new(10.0, 1000.0, 0.02, Gaussian(20.0)),
+            max_speed: GeneMutation::new(1.0, 30.0, 0.02, Gaussian(0.5)),
+            max_acceleration: GeneMutation::new(1.0, 60.0, 0.02, Gaussian(1.0)),
+            max_turn_rate: GeneMutation::new(0.5, 10.0, 0.02, Gaussian(0.2)),
+            bite_size: GeneMutation::new(0.5, 20.0, 0.02, Gaussian(0.5)),
+            hunger_rate: GeneMutation::new(0.1, 5.0, 0.02, Gaussian(0.05)),
+            max_age: GeneMutation::new(20.0, 1000.0, 0.02, Gaussian(10.0)),
+            sleep_duration: GeneMutation::new(1.0, 100.0, 0.02, Gaussian(2.0)),
+            max_offspring_count: GeneMutation::new(0.0, 20.0, 0.02, Creep(1.0)),
+            can_produce_food: GeneMutation::new(0.0, 1.0, 0.01, Creep(1.0)),
+            aggression: personality,
+            attack_range: GeneMutation::new(0.0, 10.0, 0.02, Uniform(0.5)),
+            chase_speed: GeneMutation::new(0.0, 30.0, 0.02, Gaussian(0.5)),
+        }
+    }
+}
+
+/// Where the params come from, checked regularly so edits apply while running
+#[derive(Resource)]
+pub struct ParamsSource {
+    pub path: String,
+    modified: Option<SystemTime>,
+    last_error: Option<String>, // only log an error once until it changes
+}
+
+impl ParamsSource {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            modified: None,
+            last_error: None,
+        }
+    }
+}
+
+impl Default for ParamsSource {
+    fn default() -> Self {
+        Self::new(PARAMS_PATH.into())
+    }
+}
+
+pub fn reload_params_system(
+    mut source: ResMut<ParamsSource>,
+    mut params: ResMut<SimulationParams>,
+) {
+    let modified = match fs::metadata(&source.path).and_then(|meta| meta.modified()) {
+        Ok(modified) => modified,
+        Err(e) => {
+            let error = format!("Failed to read params '{}': {}", source.path, e);
+            if source.last_error.as_ref() != Some(&error) {
+                warn!("{}, keeping the current values", error);
+                source.last_error = Some(error);
+            }
+            return;
+        }
+    };
+    if source.modified == Some(modified) {
+        return;
+    }
+    source.modified = Some(modified);
+    let result = fs::read_to_string(&source.path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| {
+            ron::from_str::<SimulationParams>(&text).map_err(|e| {
+                format!(
+                    "line {}, column {}: {}",
+                    e.position.line, e.position.col, e.code
+                )
+            })
+        })
+        .and_then(|new_params| new_params.validate().map(|()| new_params));
+    match result {
+        Ok(new_params) => {
+            *params = new_params;
+            source.last_error = None;
+            info!("Applied params from {}", source.path);
+        }
+        Err(e) => {
+            warn!(
+                "Failed to load params '{}': {}, keeping the current values",
+                source.path, e
+            );
+            source.last_error = Some(e);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+/// Every system draws from its own stream, so the order in which
+/// bevy happens to run unrelated systems does not change the results
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RngStream {
+    Setup,      // world generation and initial population
+    Perception, // target and food choice
+    GameLoop,   // movement and mating
+    Lifecycle,  // births, deaths and offspring genes
+    Input,      // mouse spawned entities
+    Plants,     // seed spreading
+}
+
+// the same generator as rand's `StdRng`, which doesn't tell how far it got
+#[derive(Resource)]
+pub struct SimRng {
+    pub seed: u64,
+    streams: HashMap<RngStream, ChaCha12Rng>,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::default(),
+        }
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha12Rng {
+        let seed = self.seed;
+        self.streams.entry(stream).or_insert_with(|| {
+            // spread the stream index over the whole seed so streams don't correlate
+            let salt = (stream as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            ChaCha12Rng::seed_from_u64(seed ^ salt)
+        })
+    }
+
+    /// How many words every stream used so far has handed out, sorted by stream
+    pub fn positions(&self) -> Vec<(RngStream, u64)> {
+        let mut positions: Vec<(RngStream, u64)> = self
+            .streams
+            .iter()
+            .map(|(&stream, rng)| (stream, rng.get_word_pos() as u64))
+            .collect();
+        positions.sort_by_key(|&(stream, _)| stream as u8);
+        positions
+    }
+
+    /// The streams of `seed` as far as `positions` got, so a loaded run draws
+    /// the same numbers it would have drawn without saving
+    pub fn restore(seed: u64, positions: &[(RngStream, u64)]) -> Self {
+        let mut sim_rng = Self::new(seed);
+        for &(stream, position) in positions {
+            sim_rng.stream(stream).set_word_pos(position as u128);
+        }
+        sim_rng
+    }
+}
+
+impl Default for SimRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
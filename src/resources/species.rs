@@ -0,0 +1,101 @@
+use crate::entities::components::{Genes, Species, SpeciesId};
+use bevy::prelude::*;
+
+const NAME_SYLLABLES: [&str; 16] = [
+    "ka", "lo", "mi", "ru", "sa", "te", "vo", "ni", "za", "pe", "gu", "ri", "do", "an", "el", "sho",
+];
+
+// a pronounceable name that only depends on the id, so naming takes nothing from the random streams
+fn species_name(id: u32) -> String {
+    let mut hash = (id as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let syllables = 2 + (hash % 2) as usize;
+    let mut name = String::new();
+    for _ in 0..syllables {
+        hash = hash.rotate_left(13).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        name.push_str(NAME_SYLLABLES[(hash >> 60) as usize]);
+    }
+    let mut chars = name.chars();
+    chars.next().unwrap().to_uppercase().chain(chars).collect()
+}
+
+// prey stay yellowish and predators reddish, neighboring ids get well separated hues
+fn species_color(id: u32, predator: bool) -> (f32, f32, f32) {
+    let spread = (id as f32 * 137.5) % 40.0;
+    let hue = if predator {
+        (340.0 + spread) % 360.0
+    } else {
+        35.0 + spread
+    };
+    let color = Color::hsl(hue, 0.9, 0.55).to_srgba();
+    (color.red, color.green, color.blue)
+}
+
+/// Every species that ever lived, the living ones with their population
+#[derive(Resource, Default, Clone, Debug)]
+pub struct SpeciesRegistry {
+    species: Vec<Species>,
+}
+
+impl SpeciesRegistry {
+    /// The species of a save file, older files have no names or colors yet
+    pub fn from_species(species: Vec<Species>) -> Self {
+        let mut registry = Self { species };
+        for species in registry.species.iter_mut() {
+            if species.name.is_empty() {
+                species.name = species_name(species.id.0);
+                species.color = species_color(species.id.0, species.predator);
+            }
+        }
+        registry
+    }
+
+    /// Adds a species with the next free id, named and colored after it
+    pub fn register(
+        &mut self,
+        genetic_min: Genes,
+        genetic_max: Genes,
+        predator: bool,
+        founded: f32,
+    ) -> &mut Species {
+        let id = self
+            .species
+            .iter()
+            .map(|species| species.id.0 + 1)
+            .max()
+            .unwrap_or(0);
+        self.species.push(Species {
+            id: SpeciesId(id),
+            genetic_min,
+            genetic_max,
+            name: species_name(id),
+            color: species_color(id, predator),
+            predator,
+            parent: None,
+            population: 0,
+            founded,
+            extinct: None,
+        });
+        self.species.last_mut().unwrap()
+    }
+
+    pub fn get(&self, id: SpeciesId) -> Option<&Species> {
+        self.species.iter().find(|species| species.id.0 == id.0)
+    }
+
+    pub fn get_mut(&mut self, id: SpeciesId) -> Option<&mut Species> {
+        self.species.iter_mut().find(|species| species.id.0 == id.0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Species> {
+        self.species.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Species> {
+        self.species.iter_mut()
+    }
+
+    /// Species with members alive as of the last census
+    pub fn living(&self) -> impl Iterator<Item = &Species> {
+        self.species.iter().filter(|species| species.population > 0)
+    }
+}
@@ -1,113 +1,518 @@
 use bevy::prelude::*;
-use ron::from_str;
+use bevy::utils::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use std::fs;
 
-use crate::entities::components::{Position, Prey, Food, EntityColor, Needs, BehaviorState};
+use super::migrations::read_save_file;
+use crate::entities::components::{
+    Age, BehaviorScores, BehaviorState, CarriedSeed, Corpse, CorpseState, EntityColor, Food,
+    FoodAmount, ForestArea, Genes, Heading, Herd, IndividualId, LastHerdId, Needs, Perception,
+    Plant, Position, Predator, Prey, SimulationClock, Species, SpeciesId, Velocity, WorldObject,
+    create_corpse, create_food, create_predator, create_prey,
+};
+use crate::entities::genome::Genome;
+use crate::resources::lineage::{LineageLedger, LineageRecord};
+use crate::resources::rng::{RngStream, SimRng};
+use crate::resources::species::SpeciesRegistry;
+
+pub const SAVE_PATH: &str = "assets/save.ron";
+
+/// Ask the simulation to write the whole world to a file at the end of the frame
+#[derive(Resource)]
+pub struct SaveRequest(pub String);
+
+/// Ask the simulation to replace the whole world with the content of a file
+#[derive(Resource)]
+pub struct LoadRequest {
+    pub path: String,
+    pub at_startup: bool, // nothing to fall back to, so a failed load stops the app
+}
+
+/// Bump this and add an upgrade step in `migrations` whenever the format changes
+pub const CURRENT_SAVE_VERSION: u32 = 14;
 
 #[derive(Serialize, Deserialize)]
-struct SimConfig {
-    entities: Vec<EntityConfig>,
+pub(super) struct SaveFile {
+    #[serde(default)] // missing in files written before the version header existed
+    pub(super) version: u32,
+    pub(super) seed: u64,
+    #[serde(default)] // added in version 14
+    pub(super) rng_streams: Vec<(RngStream, u64)>, // how far every random stream got
+    #[serde(default)] // added in version 14
+    pub(super) last_herd_id: u32,
+    #[serde(default)] // added in version 12
+    pub(super) time: f32, // the simulation clock
+    pub(super) species: Vec<Species>,
+    #[serde(default)] // added in version 6
+    pub(super) forests: Vec<ForestArea>,
+    pub(super) entities: Vec<SavedEntity>,
+    #[serde(default)] // added in version 13
+    pub(super) lineage: Vec<LineageRecord>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub(super) enum SavedKind {
+    Prey,
+    Predator,
+    Food,
+    Corpse,
+}
+
+// entities reference each other by the `id` they got in this file,
+// bevy's `Entity` values are not stable between runs
 #[derive(Serialize, Deserialize)]
-struct EntityConfig {
-    position: (f32, f32),
-    entity_type: String,
-    color: (f32, f32, f32, f32),
-    hunger: Option<f32>, // Optional for Prey only
-    behavior_state: Option<String>, // Optional for Food
+pub(super) struct SavedEntity {
+    pub(super) id: u64,
+    pub(super) kind: SavedKind,
+    pub(super) position: (f32, f32),
+    #[serde(default)] // added in version 9
+    pub(super) velocity: Option<(f32, f32)>,
+    #[serde(default)] // added in version 14, taken from the velocity before
+    pub(super) heading: Option<(f32, f32)>,
+    pub(super) color: (f32, f32, f32, f32),
+    pub(super) genes: Option<Genes>,
+    #[serde(default)] // added in version 11
+    pub(super) genome: Option<Genome>,
+    #[serde(default)] // added in version 13
+    pub(super) individual: Option<u64>, // its record in the lineage
+    pub(super) age: Option<f32>,
+    pub(super) species_id: Option<u32>,
+    pub(super) behavior_state: Option<BehaviorState>,
+    pub(super) needs: Option<SavedNeeds>,
+    pub(super) perception: Option<SavedPerception>,
+    pub(super) food_amount: Option<f32>,
+    pub(super) corpse_state: Option<CorpseState>,
+    #[serde(default)] // added in version 6
+    pub(super) plant: Option<Plant>,
+    #[serde(default)] // added in version 7
+    pub(super) carried_seed: Option<CarriedSeed>,
+    #[serde(default)] // added in version 14
+    pub(super) herd: Option<SavedHerd>,
+    #[serde(default)] // added in version 14
+    pub(super) behavior_scores: Option<BehaviorScores>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct SavedHerd {
+    pub(super) id: Option<u32>,
+    pub(super) size: u32,
+    pub(super) leader: Option<u64>,
+    pub(super) steering: (f32, f32),
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct SavedNeeds {
+    #[serde(default)] // added in version 4
+    pub(super) fear: f32,
+    pub(super) sanity: f32,
+    pub(super) hunger: f32,
+    pub(super) energy: f32,
+    #[serde(default)] // added in version 8
+    pub(super) sleep_timer: f32,
+    pub(super) mother: Option<u64>,
+    pub(super) pregnancy_timer: f32,
+    pub(super) pregnant: bool,
+    pub(super) mating_timer: f32,
+    pub(super) partner: Option<u64>,
+    #[serde(default)] // added in version 11
+    pub(super) partner_genome: Option<Genome>,
+    // replaced by `partner_genome` in version 11, only read from older files
+    #[serde(default, skip_serializing)]
+    pub(super) partner_genes: Option<Genes>,
+    #[serde(default)] // added in version 13
+    pub(super) partner_id: Option<u64>,
+    pub(super) partner_timer: f32,
+    pub(super) mate_ready: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct SavedPerception {
+    pub(super) target_food: Option<u64>,
+    #[serde(default)] // added in version 3
+    pub(super) target_prey: Option<u64>,
+    #[serde(default)] // added in version 5
+    pub(super) target_corpse: Option<u64>,
+    pub(super) visible_predators: Vec<u64>,
+    #[serde(default)] // added in version 4
+    pub(super) threats: Vec<((f32, f32), f32)>,
+    pub(super) time_since_last_sense: f32,
+    pub(super) neighbors: Vec<(f32, f32)>,
+    pub(super) target: Option<(f32, f32)>,
+    pub(super) time_since_last_target: f32,
+    pub(super) nearby_corpses: Vec<((f32, f32), f32)>,
+    pub(super) nearby_mates: Vec<u64>,
+}
+
+impl SavedNeeds {
+    pub(super) fn from_needs(needs: &Needs, ids: &HashMap<Entity, u64>) -> Self {
+        Self {
+            fear: needs.fear,
+            sanity: needs.sanity,
+            hunger: needs.hunger,
+            energy: needs.energy,
+            sleep_timer: needs.sleep_timer,
+            mother: needs.mother.and_then(|e| ids.get(&e).copied()),
+            pregnancy_timer: needs.pregnancy_timer,
+            pregnant: needs.pregnant,
+            mating_timer: needs.mating_timer,
+            partner: needs.partner.and_then(|e| ids.get(&e).copied()),
+            partner_genome: needs.partner_genome,
+            partner_genes: None,
+            partner_id: needs.partner_id.map(|id| id.0),
+            partner_timer: needs.partner_timer,
+            mate_ready: needs.mate_ready,
+        }
+    }
+
+    fn to_needs(&self, entities: &HashMap<u64, Entity>) -> Needs {
+        Needs {
+            fear: self.fear,
+            sanity: self.sanity,
+            hunger: self.hunger,
+            energy: self.energy,
+            sleep_timer: self.sleep_timer,
+            mother: self.mother.and_then(|id| entities.get(&id).copied()),
+            pregnancy_timer: self.pregnancy_timer,
+            pregnant: self.pregnant,
+            mating_timer: self.mating_timer,
+            partner: self.partner.and_then(|id| entities.get(&id).copied()),
+            partner_genome: self
+                .partner_genome
+                .or(self.partner_genes.map(Genome::homozygous)),
+            partner_id: self.partner_id.map(IndividualId),
+            partner_timer: self.partner_timer,
+            mate_ready: self.mate_ready,
+        }
+    }
 }
 
-pub fn load_config(mut commands: Commands) {
-    let config_path = "assets/save.ron";
-    match fs::read_to_string(config_path) {
-        Ok(config_str) => {
-            match from_str::<SimConfig>(&config_str) {
-                Ok(config) => {
-                    for entity in config.entities {
-                        let pos = Vec2::new(entity.position.0, entity.position.1);
-                        // let vel = Vec2::new(entity.velocity.0, entity.velocity.1);
-                        let mut entity_commands = commands.spawn((
-                            Position(pos),
-                            // Velocity(vel),
-                            EntityColor(Color::srgba(entity.color.0, entity.color.1, entity.color.2, entity.color.3)),
-                            SpriteBundle {
-                                sprite: Sprite {
-                                    color: Color::srgba(entity.color.0, entity.color.1, entity.color.2, entity.color.3),
-                                    // custom_size: Some(Vec2::new(10.0, 10.0)),
-                                    custom_size: Some(Vec2::new(2.0, 2.0)),
-                                    ..default()
-                                },
-                                transform: Transform::from_translation(pos.extend(0.0)),
-                                ..default()
-                            },
-                        ));
-                        if let Some(hunger) = entity.hunger {
-                            entity_commands.insert(Needs { hunger, ..Default::default() });
-                        }
-                        if entity.entity_type.as_str() == "Food" {
-                            entity_commands.insert(Food);
-                        }
-                        if entity.entity_type.as_str() == "Prey" {
-                            entity_commands.insert(Prey);
-                        }
-                        if let Some(state) = entity.behavior_state {
-                            let behavior_state = match state.as_str() {
-                                "SeekFood" => BehaviorState::SeekFood,
-                                "Sleep" => BehaviorState::Sleep,
-                                // "InfluencedWork" => BehaviorState::InfluencedWork,
-                                _ => {
-                                    warn!("Unknown behavior state '{}', defaulting to Sleep", state);
-                                    BehaviorState::Sleep
-                                }
-                            };
-                            entity_commands.insert(behavior_state);
-                        }
-                    }
-                    // info!("Loaded {} entities from {}", config.entities.len(), config_path);
+impl SavedHerd {
+    pub(super) fn from_herd(herd: &Herd, ids: &HashMap<Entity, u64>) -> Self {
+        Self {
+            id: herd.id,
+            size: herd.size,
+            leader: herd.leader.and_then(|e| ids.get(&e).copied()),
+            steering: (herd.steering.x, herd.steering.y),
+        }
+    }
+
+    fn to_herd(&self, entities: &HashMap<u64, Entity>) -> Herd {
+        Herd {
+            id: self.id,
+            size: self.size,
+            leader: self.leader.and_then(|id| entities.get(&id).copied()),
+            steering: Vec2::new(self.steering.0, self.steering.1),
+        }
+    }
+}
+
+impl SavedPerception {
+    pub(super) fn from_perception(perception: &Perception, ids: &HashMap<Entity, u64>) -> Self {
+        Self {
+            target_food: perception.target_food.and_then(|e| ids.get(&e).copied()),
+            target_prey: perception.target_prey.and_then(|e| ids.get(&e).copied()),
+            target_corpse: perception.target_corpse.and_then(|e| ids.get(&e).copied()),
+            visible_predators: perception
+                .visible_predators
+                .iter()
+                .filter_map(|e| ids.get(e).copied())
+                .collect(),
+            threats: perception
+                .threats
+                .iter()
+                .map(|(p, strength)| ((p.x, p.y), *strength))
+                .collect(),
+            time_since_last_sense: perception.time_since_last_sense,
+            neighbors: perception.neighbors.iter().map(|p| (p.x, p.y)).collect(),
+            target: perception.target.map(|p| (p.x, p.y)),
+            time_since_last_target: perception.time_since_last_target,
+            nearby_corpses: perception
+                .nearby_corpses
+                .iter()
+                .map(|(p, stench)| ((p.x, p.y), *stench))
+                .collect(),
+            nearby_mates: perception
+                .nearby_mates
+                .iter()
+                .filter_map(|e| ids.get(e).copied())
+                .collect(),
+        }
+    }
+
+    fn to_perception(&self, entities: &HashMap<u64, Entity>) -> Perception {
+        Perception {
+            target_food: self.target_food.and_then(|id| entities.get(&id).copied()),
+            target_prey: self.target_prey.and_then(|id| entities.get(&id).copied()),
+            target_corpse: self.target_corpse.and_then(|id| entities.get(&id).copied()),
+            visible_predators: self
+                .visible_predators
+                .iter()
+                .filter_map(|id| entities.get(id).copied())
+                .collect(),
+            threats: self
+                .threats
+                .iter()
+                .map(|(p, strength)| (Vec2::new(p.0, p.1), *strength))
+                .collect(),
+            time_since_last_sense: self.time_since_last_sense,
+            neighbors: self.neighbors.iter().map(|p| Vec2::new(p.0, p.1)).collect(),
+            target: self.target.map(|p| Vec2::new(p.0, p.1)),
+            time_since_last_target: self.time_since_last_target,
+            nearby_corpses: self
+                .nearby_corpses
+                .iter()
+                .map(|(p, stench)| (Vec2::new(p.0, p.1), *stench))
+                .collect(),
+            nearby_mates: self
+                .nearby_mates
+                .iter()
+                .filter_map(|id| entities.get(id).copied())
+                .collect(),
+        }
+    }
+}
+
+pub fn load_world_system(
+    mut commands: Commands,
+    request: Res<LoadRequest>,
+    existing_query: Query<Entity, Or<(With<WorldObject>, With<ForestArea>)>>,
+    mut sim_rng: ResMut<SimRng>,
+    mut exit: EventWriter<AppExit>,
+) {
+    commands.remove_resource::<LoadRequest>();
+    let save = match read_save_file(&request.path) {
+        Ok(save) => save,
+        Err(e) => {
+            error!("Failed to load '{}': {}", request.path, e);
+            if request.at_startup {
+                exit.send(AppExit::from_code(1));
+            }
+            return;
+        }
+    };
+
+    for entity in existing_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    // the random streams continue where they were, older files restart them from the seed
+    *sim_rng = SimRng::restore(save.seed, &save.rng_streams);
+    commands.insert_resource(LastHerdId(save.last_herd_id));
+    // files before version 12 don't say which species are predators
+    let predator_species: HashSet<u32> = save
+        .entities
+        .iter()
+        .filter(|saved| saved.kind == SavedKind::Predator)
+        .filter_map(|saved| saved.species_id)
+        .collect();
+    let mut species = save.species.clone();
+    for species in species.iter_mut() {
+        species.predator |= predator_species.contains(&species.id.0);
+    }
+    commands.insert_resource(SpeciesRegistry::from_species(species));
+    commands.insert_resource(SimulationClock(save.time));
+    // creatures from files without a ledger become founders again
+    commands.insert_resource(LineageLedger::from_records(save.lineage.clone()));
+    for forest in save.forests.iter() {
+        commands.spawn(*forest);
+    }
+
+    // reserve every entity first so references between them can be resolved
+    let entities: HashMap<u64, Entity> = save
+        .entities
+        .iter()
+        .map(|saved| (saved.id, commands.spawn_empty().id()))
+        .collect();
+    for saved in save.entities.iter() {
+        let entity = entities[&saved.id];
+        let pos = Vec2::new(saved.position.0, saved.position.1);
+        let mut entity_commands = commands.entity(entity);
+        match saved.kind {
+            SavedKind::Prey | SavedKind::Predator => {
+                let (Some(genes), Some(species_id)) = (saved.genes, saved.species_id) else {
+                    warn!("Creature {} has no genes or species, skipping it", saved.id);
+                    entity_commands.despawn();
+                    continue;
+                };
+                // the saved state replaces the defaults of a newborn, the saved color is
+                // applied below
+                if saved.kind == SavedKind::Predator {
+                    entity_commands.insert(create_predator(pos, SpeciesId(species_id), genes));
+                } else {
+                    entity_commands.insert(create_prey(pos, SpeciesId(species_id), genes));
+                }
+                if let Some(age) = saved.age {
+                    entity_commands.insert(Age(age));
+                }
+                if let Some(state) = saved.behavior_state.clone() {
+                    entity_commands.insert(state);
                 }
-                Err(e) => {
-                    warn!("Failed to parse config '{}': {}. Falling back to default initialization.", config_path, e);
+                if let Some(perception) = &saved.perception {
+                    entity_commands.insert(perception.to_perception(&entities));
+                }
+                if let Some(needs) = &saved.needs {
+                    entity_commands.insert(needs.to_needs(&entities));
+                }
+                if let Some((x, y)) = saved.velocity {
+                    // older files don't have the heading, it faces the way it was moving
+                    let velocity = Vec2::new(x, y);
+                    let heading = match saved.heading {
+                        Some((x, y)) => Vec2::new(x, y),
+                        None => velocity.normalize_or_zero(),
+                    };
+                    entity_commands.insert((Velocity(velocity), Heading(heading)));
+                }
+                if let Some(herd) = &saved.herd {
+                    entity_commands.insert(herd.to_herd(&entities));
+                }
+                if let Some(scores) = saved.behavior_scores {
+                    entity_commands.insert(scores);
+                }
+                // older files only know the expressed genes, the creature breeds true
+                entity_commands.insert(saved.genome.unwrap_or(Genome::homozygous(genes)));
+                if let Some(id) = saved.individual {
+                    entity_commands.insert(IndividualId(id));
+                }
+                if let Some(seed) = saved.carried_seed {
+                    entity_commands.insert(seed);
                 }
             }
+            SavedKind::Food => {
+                entity_commands.insert(create_food(pos, saved.food_amount.unwrap_or(0.0)));
+                if let Some(plant) = saved.plant {
+                    entity_commands.insert(plant);
+                }
+            }
+            SavedKind::Corpse => {
+                let mut corpse = create_corpse(pos, 0.0, 0.0, 0.0);
+                if let Some(corpse_state) = saved.corpse_state {
+                    corpse.5 = corpse_state;
+                }
+                entity_commands.insert(corpse);
+            }
         }
-        Err(e) => {
-            warn!("Failed to read config '{}': {}. Falling back to default initialization.", config_path, e);
-        }
+        let (r, g, b, a) = saved.color;
+        entity_commands.insert(EntityColor(Color::srgba(r, g, b, a)));
     }
+    info!(
+        "Loaded {} species and {} entities from {}",
+        save.species.len(),
+        save.entities.len(),
+        request.path
+    );
 }
 
-pub fn save_config(query: Query<(&Position, Option<&Food>, Option<&Prey>, &EntityColor, Option<&Needs>, Option<&BehaviorState>)>) {
-    let entities: Vec<EntityConfig> = query
+pub fn save_world_system(
+    mut commands: Commands,
+    request: Res<SaveRequest>,
+    sim_rng: Res<SimRng>,
+    last_herd_id: Res<LastHerdId>,
+    registry: Res<SpeciesRegistry>,
+    ledger: Res<LineageLedger>,
+    clock: Res<SimulationClock>,
+    forest_query: Query<&ForestArea>,
+    query: Query<
+        (
+            Entity,
+            &Position,
+            &EntityColor,
+            (Has<Prey>, Has<Predator>, Has<Food>, Has<Corpse>),
+            (Option<&Genes>, Option<&Genome>, Option<&IndividualId>),
+            Option<&Age>,
+            Option<&SpeciesId>,
+            Option<&BehaviorState>,
+            Option<&Needs>,
+            Option<&Perception>,
+            (
+                Option<&FoodAmount>,
+                Option<&CorpseState>,
+                Option<&Plant>,
+                Option<&CarriedSeed>,
+            ),
+            (Option<&Velocity>, Option<&Heading>),
+            (Option<&Herd>, Option<&BehaviorScores>),
+        ),
+        With<WorldObject>,
+    >,
+) {
+    commands.remove_resource::<SaveRequest>();
+    let ids: HashMap<Entity, u64> = query
         .iter()
-        .map(|(pos, _food, prey, color, needs, behavior_state)| {
-            let (r, g, b, a) = match color.0 {
-                Color::Srgba(Srgba { red, green, blue, alpha, .. }) => {
-                    (red, green, blue, alpha)
-                },
-                _ => (0.0, 0.0, 0.0, 1.0),
-            };
-            let entity_type = if prey.is_some() {
-                "Prey".to_string()
-            } else {
-                "Food".to_string()
-            };
-            EntityConfig {
-                position: (pos.0.x, pos.0.y),
-                entity_type,
-                color: (r, g, b, a),
-                hunger: needs.map(|h| h.hunger),
-                behavior_state: behavior_state.map(|s| match s {
-                    BehaviorState::SeekFood => "SeekFood".to_string(),
-                    BehaviorState::Sleep => "Sleep".to_string(),
-                    // BehaviorState::Flee => "Flee".to_string(),
-                    BehaviorState::Wander => "Wander".to_string(),
-                }),
-            }
-        })
+        .enumerate()
+        .map(|(id, (entity, ..))| (entity, id as u64))
         .collect();
-    let config = SimConfig { entities };
-    let ron_str = ron::to_string(&config).expect("Failed to serialize config");
-    fs::write("assets/save.ron", ron_str).expect("Failed to write save file");
-}
\ No newline at end of file
+    let mut entities = Vec::new();
+    for (
+        entity,
+        pos,
+        color,
+        (prey, predator, food, corpse),
+        (genes, genome, individual),
+        age,
+        species_id,
+        behavior_state,
+        needs,
+        perception,
+        (food_amount, corpse_state, plant, carried_seed),
+        (velocity, heading),
+        (herd, behavior_scores),
+    ) in query.iter()
+    {
+        let kind = if prey {
+            SavedKind::Prey
+        } else if predator {
+            SavedKind::Predator
+        } else if corpse {
+            SavedKind::Corpse
+        } else if food {
+            SavedKind::Food
+        } else {
+            continue;
+        };
+        let color = color.0.to_srgba();
+        entities.push(SavedEntity {
+            id: ids[&entity],
+            kind,
+            position: (pos.0.x, pos.0.y),
+            velocity: velocity.map(|v| (v.0.x, v.0.y)),
+            heading: heading.map(|h| (h.0.x, h.0.y)),
+            color: (color.red, color.green, color.blue, color.alpha),
+            genes: genes.copied(),
+            genome: genome.copied(),
+            individual: individual.map(|id| id.0),
+            age: age.map(|a| a.0),
+            species_id: species_id.map(|s| s.0),
+            behavior_state: behavior_state.cloned(),
+            needs: needs.map(|n| SavedNeeds::from_needs(n, &ids)),
+            perception: perception.map(|p| SavedPerception::from_perception(p, &ids)),
+            food_amount: food_amount.map(|f| f.0),
+            corpse_state: corpse_state.copied(),
+            plant: plant.copied(),
+            carried_seed: carried_seed.copied(),
+            herd: herd.map(|h| SavedHerd::from_herd(h, &ids)),
+            behavior_scores: behavior_scores.copied(),
+        });
+    }
+    let save = SaveFile {
+        version: CURRENT_SAVE_VERSION,
+        seed: sim_rng.seed,
+        rng_streams: sim_rng.positions(),
+        last_herd_id: last_herd_id.0,
+        time: clock.0,
+        species: registry.iter().cloned().collect(),
+        forests: forest_query.iter().copied().collect(),
+        entities,
+        lineage: ledger.iter().cloned().collect(),
+    };
+    let result = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|ron_str| fs::write(&request.0, ron_str).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => info!(
+            "Saved {} species and {} entities to {}",
+            save.species.len(),
+            save.entities.len(),
+            request.0
+        ),
+        Err(e) => error!("Failed to save '{}': {}", request.0, e),
+    }
+}
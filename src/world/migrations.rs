@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+use super::config::{
+    CURRENT_SAVE_VERSION, SaveFile, SavedEntity, SavedKind, SavedNeeds, SavedPerception,
+};
+use crate::entities::components::{BehaviorState, Genes, Needs, Perception, Plant};
+use crate::entities::genome::clamp_genes;
+use crate::resources::params::GeneMutations;
+use crate::resources::species::SpeciesRegistry;
+
+// Save format history:
+// 1 - `(entities: [(position, entity_type, color, hunger, behavior_state)])`, as in assets/init.ron
+// 2 - full world with seed, species and every component, entities linked by saved id
+// 3 - predators, hunting genes and `target_prey`, all filled with defaults when missing
+// 4 - fear, boldness and panic threshold, the `Flee` state and perceived threats
+// 5 - `target_corpse` for scavengers
+// 6 - forest areas and plants that regrow, older food becomes plants outside any area
+// 7 - seeds carried by food producers
+// 8 - sleep duration gene and sleep timer
+// 9 - velocity, acceleration and turning genes
+// 10 - field of view gene
+// 11 - diploid genome, the genes are what it expresses
+// 12 - species registry with names, colors, population, founding and extinction, and the clock
+// 13 - lineage ledger, creatures keep their individual id and their partner's
+// 14 - random stream positions, herds, behavior scores and headings, a load resumes the run
+//
+// Versions 2 and up are all read into `SaveFile`, so a new version may only add fields with
+// `#[serde(default)]`. Renaming, removing or reinterpreting a field needs its own upgrade
+// step after parsing, like `plant_food`.
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        col: usize,
+        message: String,
+    },
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Parse { line, col, message } => {
+                write!(f, "line {}, column {}: {}", line, col, message)
+            }
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save format version {} is not supported, this build reads versions 1 to {}",
+                version, CURRENT_SAVE_VERSION
+            ),
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(text: &str) -> Result<T, SaveError> {
+    parse_with(ron::Options::default(), text)
+}
+
+fn parse_with<T: DeserializeOwned>(options: ron::Options, text: &str) -> Result<T, SaveError> {
+    options.from_str(text).map_err(|e| SaveError::Parse {
+        line: e.position.line,
+        col: e.position.col,
+        message: e.code.to_string(),
+    })
+}
+
+// only the fields needed to tell the versions apart, everything else is ignored
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: Option<u32>,
+    seed: Option<u64>,
+}
+
+/// Reads a save file of any known version and upgrades it to the current one
+pub(super) fn read_save_file(path: &str) -> Result<SaveFile, SaveError> {
+    let text = std::fs::read_to_string(path).map_err(SaveError::Io)?;
+    // `version: 2` instead of `version: Some(2)`
+    let header_options =
+        ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME);
+    let header: SaveHeader = parse_with(header_options, &text)?;
+    // files written before the header existed: version 2 already had a seed
+    let version = header
+        .version
+        .unwrap_or(if header.seed.is_some() { 2 } else { 1 });
+    let mut save = match version {
+        1 => upgrade_v1(parse(&text)?),
+        // only fields were added since version 2, missing ones get their defaults
+        // and the steps below fix up what the defaults get wrong
+        2..=CURRENT_SAVE_VERSION => parse::<SaveFile>(&text)?,
+        _ => return Err(SaveError::UnsupportedVersion(version)),
+    };
+    if version < 6 {
+        plant_food(&mut save);
+    }
+    if version != CURRENT_SAVE_VERSION {
+        info!(
+            "Upgraded '{}' from save format version {} to {}",
+            path, version, CURRENT_SAVE_VERSION
+        );
+    }
+    save.version = CURRENT_SAVE_VERSION;
+    Ok(save)
+}
+
+// food never regrew before version 6, turn it into plants so old worlds don't starve
+fn plant_food(save: &mut SaveFile) {
+    for entity in save.entities.iter_mut() {
+        if entity.kind == SavedKind::Food && entity.plant.is_none() {
+            entity.plant = Some(Plant {
+                area: None,
+                max_amount: entity.food_amount.unwrap_or(0.0),
+                seed_timer: 0.0,
+            });
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SaveFileV1 {
+    entities: Vec<EntityConfigV1>,
+}
+
+#[derive(Deserialize)]
+struct EntityConfigV1 {
+    position: (f32, f32),
+    #[serde(default, deserialize_with = "nullable")]
+    velocity: Option<(f32, f32)>,
+    entity_type: EntityTypeV1,
+    color: (f32, f32, f32, f32),
+    #[serde(default, deserialize_with = "nullable")]
+    hunger: Option<f32>,
+    #[serde(default, deserialize_with = "nullable")]
+    behavior_state: Option<BehaviorStateV1>,
+}
+
+// version 1 files were written by hand, with `null` for a missing value and the
+// value itself for a present one, `None` and `Some(..)` are accepted as well
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    match ron::Value::deserialize(deserializer)? {
+        ron::Value::Unit | ron::Value::Option(None) => Ok(None),
+        ron::Value::Option(Some(value)) => value.into_rust().map(Some).map_err(D::Error::custom),
+        value => value.into_rust().map(Some).map_err(D::Error::custom),
+    }
+}
+
+// unknown names are rejected while parsing, so the error points at the offending line
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+enum EntityTypeV1 {
+    Prey,
+    Food,
+}
+
+impl TryFrom<String> for EntityTypeV1 {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "Prey" => Ok(EntityTypeV1::Prey),
+            "Food" => Ok(EntityTypeV1::Food),
+            _ => Err(format!("unknown entity type '{}'", value)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct BehaviorStateV1(BehaviorState);
+
+impl TryFrom<String> for BehaviorStateV1 {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "SeekFood" => Ok(BehaviorStateV1(BehaviorState::SeekFood)),
+            "Sleep" => Ok(BehaviorStateV1(BehaviorState::Sleep)),
+            "Wander" => Ok(BehaviorStateV1(BehaviorState::Wander)),
+            _ => Err(format!("unknown behavior state '{}'", value)),
+        }
+    }
+}
+
+// version 1 had no genes or species, so every prey joins one species
+// generated from a fixed seed, which keeps the upgrade reproducible
+fn upgrade_v1(v1: SaveFileV1) -> SaveFile {
+    let seed = 0;
+    let mut rng = StdRng::seed_from_u64(seed);
+    // the built-in gene ranges, an edited params file must not change the result
+    let mutations = GeneMutations::default();
+    let genetic_min = clamp_genes(&Genes::random(&mut rng), &mutations);
+    let genetic_max = genetic_min.random_variation(&mutations, &mut rng);
+    let mut registry = SpeciesRegistry::default();
+    let species = registry
+        .register(genetic_min, genetic_max, false, 0.0)
+        .clone();
+    let no_links = HashMap::default();
+    let entities = v1
+        .entities
+        .into_iter()
+        .enumerate()
+        .map(|(id, entity)| {
+            let is_prey = matches!(entity.entity_type, EntityTypeV1::Prey);
+            let genome = is_prey.then(|| species.random_genome(&mutations, &mut rng));
+            SavedEntity {
+                id: id as u64,
+                kind: if is_prey {
+                    SavedKind::Prey
+                } else {
+                    SavedKind::Food
+                },
+                position: entity.position,
+                velocity: entity.velocity.filter(|_| is_prey),
+                color: entity.color,
+                genes: genome.map(|genome| genome.express(&mutations)),
+                genome,
+                individual: None,
+                heading: None,
+                herd: None,
+                behavior_scores: None,
+                age: is_prey.then_some(0.0),
+                species_id: is_prey.then_some(species.id.0),
+                behavior_state: entity
+                    .behavior_state
+                    .map(|state| state.0)
+                    .or(is_prey.then_some(BehaviorState::Wander)),
+                needs: is_prey.then(|| {
+                    let mut needs = SavedNeeds::from_needs(&Needs::default(), &no_links);
+                    needs.hunger = entity.hunger.unwrap_or(0.0);
+                    needs
+                }),
+                perception: is_prey
+                    .then(|| SavedPerception::from_perception(&Perception::default(), &no_links)),
+                // version 1 did not store how much food was left
+                food_amount: (!is_prey).then_some(50.0),
+                corpse_state: None,
+                plant: None,
+                carried_seed: None,
+            }
+        })
+        .collect();
+    SaveFile {
+        version: CURRENT_SAVE_VERSION,
+        seed,
+        rng_streams: Vec::new(),
+        last_herd_id: 0,
+        time: 0.0,
+        species: vec![species],
+        forests: Vec::new(),
+        entities,
+        lineage: Vec::new(),
+    }
+}
@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+
+use crate::entities::components::Genes;
+
+// A scenario describes the world a run starts from, see assets/scenarios/
+// Ranges are inclusive `(min, max)` pairs, a fixed value is written as `(n, n)`.
+
+#[derive(Resource, Deserialize, Clone, Debug)]
+pub struct Scenario {
+    pub world_size: (f32, f32), // width, height
+    pub forests: ForestSettings,
+    // species generated from random genes, like the original hardcoded setup
+    #[serde(default)]
+    pub random_species: Option<RandomSpeciesSettings>,
+    // predator species generated from random genes
+    #[serde(default)]
+    pub random_predators: Option<RandomSpeciesSettings>,
+    // species with hand picked gene ranges
+    #[serde(default)]
+    pub species: Vec<SpeciesSettings>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ForestSettings {
+    pub count: (u32, u32),       // how many forest areas
+    pub size: (f32, f32),        // side length of a forest area
+    pub food_amount: (f32, f32), // food in every plant
+    #[serde(default = "default_capacity")]
+    pub capacity: (u32, u32), // most plants a forest area can carry
+    pub region: Option<SpawnRegion>, // where forests may grow, whole world if None
+}
+
+fn default_capacity() -> (u32, u32) {
+    (150, 250)
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RandomSpeciesSettings {
+    pub count: (u32, u32),
+    pub population: u32, // spread randomly over all random species
+    pub region: Option<SpawnRegion>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpeciesSettings {
+    #[serde(default)]
+    pub name: Option<String>, // generated from its id if missing
+    pub genetic_min: Genes,
+    pub genetic_max: Genes,
+    pub population: u32,
+    pub region: Option<SpawnRegion>,
+    #[serde(default)]
+    pub predator: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct SpawnRegion {
+    pub min: (f32, f32),
+    pub max: (f32, f32),
+}
+
+impl SpawnRegion {
+    pub fn whole_world(world_size: (f32, f32)) -> Self {
+        Self {
+            min: (0.0, 0.0),
+            max: world_size,
+        }
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            world_size: (800.0, 600.0),
+            forests: ForestSettings {
+                count: (20, 29),
+                size: (100.0, 200.0),
+                food_amount: (10.0, 100.0),
+                capacity: default_capacity(),
+                region: None,
+            },
+            random_species: Some(RandomSpeciesSettings {
+                count: (5, 9),
+                population: 2000,
+                region: None,
+            }),
+            random_predators: Some(RandomSpeciesSettings {
+                count: (1, 2),
+                population: 40,
+                region: None,
+            }),
+            species: Vec::new(),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Scenario, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let scenario: Scenario = ron::from_str(&text).map_err(|e| {
+            format!(
+                "line {}, column {}: {}",
+                e.position.line, e.position.col, e.code
+            )
+        })?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let (width, height) = self.world_size;
+        if !(width.is_finite() && width > 0.0 && height.is_finite() && height > 0.0) {
+            return Err("world_size must be positive".into());
+        }
+        let forests = &self.forests;
+        check_range("forests.count", forests.count)?;
+        check_extent("forests.size", forests.size)?;
+        check_finite("forests.food_amount", forests.food_amount)?;
+        check_range("forests.capacity", forests.capacity)?;
+        check_region("forests.region", forests.region)?;
+        if let Some(random_species) = &self.random_species {
+            check_range("random_species.count", random_species.count)?;
+            if random_species.count.0 == 0 && random_species.population > 0 {
+                return Err("random_species.count must allow at least one species".into());
+            }
+            check_region("random_species.region", random_species.region)?;
+        }
+        if let Some(random_predators) = &self.random_predators {
+            check_range("random_predators.count", random_predators.count)?;
+            if random_predators.count.0 == 0 && random_predators.population > 0 {
+                return Err("random_predators.count must allow at least one species".into());
+            }
+            check_region("random_predators.region", random_predators.region)?;
+        }
+        for (i, species) in self.species.iter().enumerate() {
+            check_region(&format!("species[{}].region", i), species.region)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_range<T: PartialOrd + std::fmt::Debug>(name: &str, range: (T, T)) -> Result<(), String> {
+    if range.0 > range.1 {
+        return Err(format!(
+            "{} has min {:?} above max {:?}",
+            name, range.0, range.1
+        ));
+    }
+    Ok(())
+}
+
+// NaN passes every comparison in check_range, so float ranges are checked here first
+fn check_finite(name: &str, range: (f32, f32)) -> Result<(), String> {
+    if !(range.0.is_finite() && range.1.is_finite()) {
+        return Err(format!("{} must be finite", name));
+    }
+    check_range(name, range)
+}
+
+// sizes something is spread over, an empty one has nowhere to put it
+fn check_extent(name: &str, range: (f32, f32)) -> Result<(), String> {
+    check_finite(name, range)?;
+    if range.0 <= 0.0 {
+        return Err(format!("{} must be positive", name));
+    }
+    Ok(())
+}
+
+fn check_region(name: &str, region: Option<SpawnRegion>) -> Result<(), String> {
+    if let Some(region) = region {
+        check_finite(&format!("{}.x", name), (region.min.0, region.max.0))?;
+        check_finite(&format!("{}.y", name), (region.min.1, region.max.1))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use life_sim_2d::entities::components::Genes;
+use life_sim_2d::entities::genome::Genome;
+use life_sim_2d::resources::params::{GeneMutations, MutationStep, SimulationParams};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+#[test]
+fn a_homozygous_genome_expresses_its_genes() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let genes = Genes::random_predator(&mut rng);
+    let expressed = Genome::homozygous(genes).express(&GeneMutations::default());
+    assert_eq!(format!("{:?}", expressed), format!("{:?}", genes));
+}
+
+#[test]
+fn mutation_keeps_variation_in_a_closed_population() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut params = SimulationParams::default();
+    params.gene_mutations.max_speed.rate = 0.2;
+    let genes = Genes::random(&mut rng);
+    let founder = Genome::homozygous(genes);
+    // without mutation two copies of the same founder could only ever have clones
+    let mut population = vec![founder; 20];
+    for _ in 0..10 {
+        population = (0..population.len())
+            .map(|i| {
+                let mother = &population[i];
+                let father = &population[(i + 1) % population.len()];
+                Genome::offspring(mother, father, &params, &mut rng)
+            })
+            .collect();
+    }
+    let speeds: Vec<f32> = population
+        .iter()
+        .map(|genome| genome.express(&params.gene_mutations).max_speed)
+        .collect();
+    assert!(speeds.iter().any(|&speed| speed != genes.max_speed));
+}
+
+#[test]
+fn mutated_genes_stay_in_their_range() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut params = SimulationParams::default();
+    // every allele mutates by far more than the whole range
+    params.gene_mutations.vision_range.rate = 1.0;
+    params.gene_mutations.vision_range.step = MutationStep::Gaussian(5000.0);
+    params.gene_mutations.sociality.rate = 1.0;
+    params.gene_mutations.sociality.step = MutationStep::Uniform(10.0);
+    params.gene_mutations.max_offspring_count.rate = 1.0;
+    params.gene_mutations.max_offspring_count.step = MutationStep::Creep(30.0);
+    let bounds = params.gene_mutations.clone();
+    let mut genome = Genome::homozygous(Genes::random(&mut rng));
+    for _ in 0..100 {
+        genome = Genome::offspring(&genome, &genome, &params, &mut rng);
+        let genes = genome.express(&bounds);
+        assert!((bounds.vision_range.min..=bounds.vision_range.max).contains(&genes.vision_range));
+        assert!((bounds.sociality.min..=bounds.sociality.max).contains(&genes.sociality));
+        assert!(genes.max_offspring_count as f32 <= bounds.max_offspring_count.max);
+    }
+    // a wide species range is cut to the valid one too
+    let variation = Genes::random(&mut rng).random_variation(&bounds, &mut rng);
+    assert!(variation.sociality >= 0.0 && variation.vision_range >= bounds.vision_range.min);
+}
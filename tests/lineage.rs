@@ -0,0 +1,69 @@
+use life_sim_2d::entities::components::{Genes, IndividualId, SpeciesId};
+use life_sim_2d::resources::lineage::LineageLedger;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+// two founders with a daughter, and a granddaughter from the daughter and a third founder
+fn small_family() -> LineageLedger {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut ledger = LineageLedger::default();
+    let mut genes = || Genes::random(&mut rng);
+    let mother = ledger.record_birth(None, None, SpeciesId(0), false, genes(), 0.0);
+    let father = ledger.record_birth(None, None, SpeciesId(0), false, genes(), 0.0);
+    let daughter = ledger.record_birth(
+        Some(mother),
+        Some(father),
+        SpeciesId(0),
+        false,
+        genes(),
+        5.0,
+    );
+    let stranger = ledger.record_birth(None, None, SpeciesId(1), false, genes(), 2.0);
+    ledger.record_birth(
+        Some(daughter),
+        Some(stranger),
+        SpeciesId(1),
+        false,
+        genes(),
+        8.0,
+    );
+    ledger.record_death(mother, 9.0);
+    ledger
+}
+
+#[test]
+fn generations_count_from_the_older_parent() {
+    let ledger = small_family();
+    let generations: Vec<u32> = ledger.iter().map(|record| record.generation).collect();
+    assert_eq!(generations, [0, 0, 1, 0, 2]);
+    let granddaughter = ledger.get(IndividualId(4)).unwrap();
+    assert_eq!(granddaughter.mother, Some(IndividualId(2)));
+    assert_eq!(granddaughter.father, Some(IndividualId(3)));
+    assert_eq!(ledger.get(IndividualId(0)).unwrap().died, Some(9.0));
+}
+
+#[test]
+fn newick_nests_children_below_their_mother() {
+    let newick = small_family().to_newick();
+    assert_eq!(
+        newick,
+        "(((i4:3[&&NHX:species=1:generation=2:father=3])\
+         i2:5[&&NHX:species=0:generation=1:father=1])\
+         i0:0[&&NHX:species=0:generation=0:died=9],\
+         i1:0[&&NHX:species=0:generation=0],\
+         i3:0[&&NHX:species=1:generation=0]);"
+    );
+}
+
+#[test]
+fn json_tree_keeps_whole_records() {
+    let json = small_family().to_json().unwrap();
+    let roots: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let roots = roots.as_array().unwrap();
+    assert_eq!(roots.len(), 3);
+    let daughter = &roots[0]["children"][0];
+    assert_eq!(daughter["id"], 2);
+    assert_eq!(daughter["father"], 1);
+    assert_eq!(daughter["children"][0]["generation"], 2);
+    assert!(daughter["genes"]["max_speed"].is_number());
+}
@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+use life_sim_2d::resources::params::{ParamsSource, SimulationParams, reload_params_system};
+use std::fs;
+
+fn params_file(name: &str, text: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, text).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn an_invalid_reload_keeps_the_current_params() {
+    let valid = params_file("life_sim_2d_valid_params.ron", "(sanity_gain_rate: 0.5)");
+    let invalid = params_file(
+        "life_sim_2d_invalid_params.ron",
+        "(sanity_gain_rate: 0.7, max_hunger: 0.0)",
+    );
+    let mut app = App::new();
+    app.init_resource::<SimulationParams>()
+        .insert_resource(ParamsSource::new(valid.clone()))
+        .add_systems(Update, reload_params_system);
+    app.update();
+    assert_eq!(
+        app.world().resource::<SimulationParams>().sanity_gain_rate,
+        0.5
+    );
+
+    app.insert_resource(ParamsSource::new(invalid.clone()));
+    app.update();
+    let params = app.world().resource::<SimulationParams>();
+    assert_eq!(params.sanity_gain_rate, 0.5);
+    assert_eq!(params.max_hunger, SimulationParams::default().max_hunger);
+
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(invalid).unwrap();
+}
@@ -0,0 +1,40 @@
+use life_sim_2d::world::scenario::Scenario;
+use std::fs;
+
+fn load(name: &str, text: &str) -> Result<Scenario, String> {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, text).unwrap();
+    let scenario = Scenario::load(&path.to_string_lossy());
+    fs::remove_file(path).unwrap();
+    scenario
+}
+
+fn with_forests(world_size: &str, size: &str, region: &str) -> String {
+    format!(
+        "(world_size: {}, forests: (count: (1, 2), size: {}, food_amount: (10.0, 20.0), region: {}))",
+        world_size, size, region
+    )
+}
+
+#[test]
+fn a_scenario_without_room_to_spawn_in_is_rejected() {
+    let name = "life_sim_2d_scenario.ron";
+    assert!(
+        load(
+            name,
+            &with_forests("(800.0, 600.0)", "(100.0, 200.0)", "None")
+        )
+        .is_ok()
+    );
+    for world_size in ["(NaN, 600.0)", "(800.0, inf)", "(0.0, 600.0)"] {
+        let text = with_forests(world_size, "(100.0, 200.0)", "None");
+        assert!(load(name, &text).is_err(), "{} was accepted", world_size);
+    }
+    for size in ["(NaN, 200.0)", "(100.0, inf)", "(0.0, 200.0)"] {
+        let text = with_forests("(800.0, 600.0)", size, "None");
+        assert!(load(name, &text).is_err(), "{} was accepted", size);
+    }
+    let region = "Some((min: (0.0, 0.0), max: (NaN, 600.0)))";
+    let text = with_forests("(800.0, 600.0)", "(100.0, 200.0)", region);
+    assert!(load(name, &text).is_err());
+}
@@ -0,0 +1,651 @@
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use life_sim_2d::entities::components::{
+    Age, BehaviorScores, BehaviorState, CarriedSeed, Food, FoodAmount, ForestArea, Genes, Heading,
+    Herd, IndividualId, LivingEntity, Needs, Perception, Plant, Position, Predator, Prey,
+    SimulationClock, SpeciesId, Velocity, create_corpse, create_plant, create_predator,
+    create_prey,
+};
+use life_sim_2d::entities::genome::Genome;
+use life_sim_2d::plugins::simulation::SimulationPlugin;
+use life_sim_2d::resources::lineage::LineageLedger;
+use life_sim_2d::resources::params::{ParamsSource, SimulationParams};
+use life_sim_2d::resources::rng::SimRng;
+use life_sim_2d::resources::species::SpeciesRegistry;
+use life_sim_2d::world::config::{LoadRequest, SaveRequest};
+use life_sim_2d::world::scenario::{
+    ForestSettings, RandomSpeciesSettings, Scenario, SpeciesSettings,
+};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::time::Duration;
+
+// a smaller world than the default one keeps the tests fast
+fn headless_app(seed: u64) -> App {
+    let scenario = Scenario {
+        random_species: Some(RandomSpeciesSettings {
+            count: (3, 3),
+            population: 200,
+            region: None,
+        }),
+        random_predators: Some(RandomSpeciesSettings {
+            count: (1, 1),
+            population: 10,
+            region: None,
+        }),
+        ..Default::default()
+    };
+    app_with_scenario(scenario, seed)
+}
+
+// nothing but what a test spawns itself
+fn empty_app() -> App {
+    let defaults = Scenario::default();
+    let scenario = Scenario {
+        forests: ForestSettings {
+            count: (0, 0),
+            ..defaults.forests
+        },
+        random_species: None,
+        random_predators: None,
+        ..defaults
+    };
+    let mut app = app_with_scenario(scenario, 1);
+    // the first update only runs the startup systems, the time starts with the next one
+    app.update();
+    app
+}
+
+fn app_with_scenario(scenario: Scenario, seed: u64) -> App {
+    let mut app = App::new();
+    app.insert_resource(scenario)
+        .insert_resource(SimRng::new(seed))
+        .add_plugins(MinimalPlugins)
+        // the same amount of simulated time every update, like the headless runner
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .add_plugins(SimulationPlugin);
+    app
+}
+
+fn set_params(app: &mut App, params: SimulationParams) {
+    // no file to reload them from, so they stay as set
+    app.insert_resource(params)
+        .insert_resource(ParamsSource::new(String::new()));
+}
+
+// the same genes every time, a test sets the ones it looks at
+fn test_genes(predator: bool) -> Genes {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut genes = if predator {
+        Genes::random_predator(&mut rng)
+    } else {
+        Genes::random(&mut rng)
+    };
+    genes.laziness = 0.0; // looks around every step
+    genes
+}
+
+fn spawn_creature(app: &mut App, pos: Vec2, genes: Genes, predator: bool) -> Entity {
+    let genome = Genome::homozygous(genes);
+    let species_id = SpeciesId(predator as u32);
+    if predator {
+        let creature = create_predator(pos, species_id, genes);
+        app.world_mut().spawn((creature, genome)).id()
+    } else {
+        let creature = create_prey(pos, species_id, genes);
+        app.world_mut().spawn((creature, genome)).id()
+    }
+}
+
+fn prey_positions(app: &mut App) -> Vec<(f32, f32)> {
+    let mut query = app.world_mut().query_filtered::<&Position, With<Prey>>();
+    let mut positions: Vec<(f32, f32)> = query
+        .iter(app.world())
+        .map(|pos| (pos.0.x, pos.0.y))
+        .collect();
+    positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    positions
+}
+
+#[test]
+fn startup_spawns_the_scenario() {
+    let mut app = headless_app(1);
+    app.update();
+
+    let species = app.world().resource::<SpeciesRegistry>().iter().count();
+    assert_eq!(species, 4);
+    assert_eq!(prey_positions(&mut app).len(), 200);
+    let predators = app
+        .world_mut()
+        .query_filtered::<(), With<Predator>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(predators, 10);
+}
+
+#[test]
+fn same_seed_gives_the_same_run() {
+    let mut first = headless_app(7);
+    let mut second = headless_app(7);
+    for _ in 0..120 {
+        first.update();
+        second.update();
+    }
+
+    let positions = prey_positions(&mut first);
+    assert!(!positions.is_empty());
+    assert_eq!(positions, prey_positions(&mut second));
+}
+
+#[test]
+fn herds_agree_on_their_size_and_leader() {
+    let mut app = headless_app(3);
+    for _ in 0..60 {
+        app.update();
+    }
+
+    let herds: Vec<Herd> = app
+        .world_mut()
+        .query::<&Herd>()
+        .iter(app.world())
+        .copied()
+        .collect();
+    assert!(herds.iter().any(|herd| herd.id.is_some()));
+    for herd in herds.iter() {
+        let Some(id) = herd.id else {
+            assert_eq!(herd.size, 1);
+            assert!(herd.leader.is_none());
+            continue;
+        };
+        let members: Vec<&Herd> = herds.iter().filter(|other| other.id == Some(id)).collect();
+        assert_eq!(members.len() as u32, herd.size);
+        assert!(members.iter().all(|other| other.leader == herd.leader));
+    }
+}
+
+#[test]
+fn diverged_creatures_split_into_a_new_species() {
+    let mut app = headless_app(5);
+    app.update();
+
+    // a few prey of one species get genes far from their relatives
+    let mut query = app
+        .world_mut()
+        .query_filtered::<(Entity, &mut Genes, &SpeciesId), With<Prey>>();
+    let (_, first_genes, original) = query.iter(app.world()).next().unwrap();
+    let original = original.0;
+    let mut diverged = *first_genes;
+    diverged.sociality = 1.0 - diverged.sociality;
+    diverged.curiosity = 1.0 - diverged.curiosity;
+    diverged.greed = 1.0 - diverged.greed;
+    diverged.laziness = 1.0 - diverged.laziness;
+    diverged.vision_range += 400.0;
+    diverged.max_speed += 15.0;
+    let mut mutants = Vec::new();
+    for (entity, mut genes, species_id) in query.iter_mut(app.world_mut()) {
+        if species_id.0 == original && mutants.len() < 5 {
+            *genes = diverged;
+            mutants.push(entity);
+        }
+    }
+    // the clustering runs every `speciation_interval` simulated seconds
+    for _ in 0..400 {
+        app.update();
+    }
+
+    let ids: Vec<u32> = mutants
+        .iter()
+        .filter_map(|&entity| app.world().get::<SpeciesId>(entity))
+        .map(|species_id| species_id.0)
+        .collect();
+    assert!(!ids.is_empty());
+    assert!(ids.iter().all(|&id| id == ids[0] && id != original));
+    let registry = app.world().resource::<SpeciesRegistry>();
+    let species = registry.get(SpeciesId(ids[0])).unwrap();
+    assert_eq!(species.parent.map(|parent| parent.0), Some(original));
+    assert!(species.population >= ids.len() as u32);
+}
+
+#[test]
+fn a_part_that_splits_off_toward_another_species_joins_it() {
+    let mut app = empty_app();
+    let alike = test_genes(false);
+    let mut distant = alike;
+    distant.sociality = 1.0 - distant.sociality;
+    distant.curiosity = 1.0 - distant.curiosity;
+    distant.greed = 1.0 - distant.greed;
+    distant.laziness = 1.0 - distant.laziness;
+    distant.vision_range += 400.0;
+    distant.max_speed += 15.0;
+    let mut registry = app.world_mut().resource_mut::<SpeciesRegistry>();
+    let small = registry.register(alike, alike, false, 0.0).id;
+    let big = registry.register(distant, distant, false, 0.0).id;
+    // the big species has a part that became like the small one, bigger than it
+    let mut spawn = |count: usize, species_id: SpeciesId, genes: Genes| {
+        (0..count)
+            .map(|i| {
+                let pos = Vec2::new(100.0 + 10.0 * i as f32, 300.0);
+                let creature = create_prey(pos, species_id, genes);
+                app.world_mut()
+                    .spawn((creature, Genome::homozygous(genes)))
+                    .id()
+            })
+            .collect::<Vec<Entity>>()
+    };
+    let small_members = spawn(5, small, alike);
+    let converged = spawn(20, big, alike);
+    let big_members = spawn(30, big, distant);
+    // the clustering runs every `speciation_interval` simulated seconds
+    for _ in 0..400 {
+        app.update();
+    }
+
+    let species_of = |app: &App, entities: &[Entity]| -> Vec<u32> {
+        entities
+            .iter()
+            .filter_map(|&entity| app.world().get::<SpeciesId>(entity))
+            .map(|species_id| species_id.0)
+            .collect()
+    };
+    assert!(
+        species_of(&app, &small_members)
+            .iter()
+            .all(|&id| id == small.0)
+    );
+    let converged = species_of(&app, &converged);
+    assert!(!converged.is_empty());
+    assert!(converged.iter().all(|&id| id == small.0));
+    assert!(species_of(&app, &big_members).iter().all(|&id| id == big.0));
+    // no species was founded for the part that left
+    assert_eq!(app.world().resource::<SpeciesRegistry>().iter().count(), 2);
+}
+
+#[test]
+fn the_census_counts_every_living_creature() {
+    let mut app = headless_app(6);
+    for _ in 0..200 {
+        app.update();
+    }
+
+    let living = app
+        .world_mut()
+        .query_filtered::<(), With<LivingEntity>>()
+        .iter(app.world())
+        .count();
+    let registry = app.world().resource::<SpeciesRegistry>();
+    let counted: u32 = registry.iter().map(|species| species.population).sum();
+    assert_eq!(counted as usize, living);
+    // species without members are marked extinct
+    assert!(
+        registry
+            .iter()
+            .all(|species| (species.population == 0) == species.extinct.is_some())
+    );
+}
+
+#[test]
+fn a_species_that_never_had_members_is_extinct() {
+    let genes = test_genes(false);
+    let scenario = Scenario {
+        species: vec![SpeciesSettings {
+            name: None,
+            genetic_min: genes,
+            genetic_max: genes,
+            population: 0,
+            region: None,
+            predator: false,
+        }],
+        random_species: None,
+        random_predators: None,
+        ..Default::default()
+    };
+    let mut app = app_with_scenario(scenario, 1);
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let registry = app.world().resource::<SpeciesRegistry>();
+    let species = registry.get(SpeciesId(0)).unwrap();
+    assert_eq!(species.population, 0);
+    assert!(species.extinct.is_some());
+}
+
+#[test]
+fn the_ledger_knows_every_creature_alive_or_dead() {
+    let mut app = headless_app(7);
+    for _ in 0..300 {
+        app.update();
+    }
+
+    let mut ids: Vec<u64> = app
+        .world_mut()
+        .query_filtered::<&IndividualId, With<LivingEntity>>()
+        .iter(app.world())
+        .map(|id| id.0)
+        .collect();
+    let ledger = app.world().resource::<LineageLedger>();
+    for &id in ids.iter() {
+        assert!(ledger.get(IndividualId(id)).unwrap().died.is_none());
+    }
+    // the dead ones had their death recorded
+    let alive = ledger.iter().filter(|record| record.died.is_none()).count();
+    assert_eq!(alive, ids.len());
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), alive);
+}
+
+#[test]
+fn a_loaded_save_resumes_the_same_run() {
+    let path = std::env::temp_dir().join("life_sim_2d_resume_test.ron");
+    let path = path.to_string_lossy().into_owned();
+    let mut uninterrupted = headless_app(8);
+    let mut saved = headless_app(8);
+    for _ in 0..150 {
+        uninterrupted.update();
+        saved.update();
+    }
+    // the save is written at the end of the update
+    saved.insert_resource(SaveRequest(path.clone()));
+    saved.update();
+    uninterrupted.update();
+
+    let mut loaded = headless_app(8);
+    loaded.insert_resource(LoadRequest {
+        path: path.clone(),
+        at_startup: true,
+    });
+    // the first update of an app doesn't advance the time, it only loads the file
+    loaded.update();
+    for _ in 0..150 {
+        uninterrupted.update();
+        loaded.update();
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    let positions = prey_positions(&mut uninterrupted);
+    assert!(!positions.is_empty());
+    assert_eq!(positions, prey_positions(&mut loaded));
+}
+
+#[test]
+fn the_version_1_init_file_still_loads() {
+    let mut app = headless_app(9);
+    app.insert_resource(LoadRequest {
+        path: "assets/init.ron".to_string(),
+        at_startup: true,
+    });
+    app.update();
+
+    let prey: Vec<(&Position, &BehaviorState)> = app
+        .world_mut()
+        .query_filtered::<(&Position, &BehaviorState), With<Prey>>()
+        .iter(app.world())
+        .collect();
+    assert_eq!(prey.len(), 1);
+    assert_eq!(prey[0].0.0, Vec2::new(500.0, 500.0));
+    assert!(matches!(prey[0].1, BehaviorState::Sleep));
+    let food = app
+        .world_mut()
+        .query_filtered::<(), With<Food>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(food, 1);
+}
+
+#[test]
+fn prey_only_see_predators_inside_their_field_of_view() {
+    let mut app = empty_app();
+    let mut genes = test_genes(false);
+    genes.vision_range = 300.0;
+    genes.field_of_view = 1.0;
+    let prey = spawn_creature(&mut app, Vec2::new(400.0, 300.0), genes, false);
+    app.world_mut().entity_mut(prey).insert(Heading(Vec2::X));
+    let ahead = spawn_creature(&mut app, Vec2::new(450.0, 300.0), test_genes(true), true);
+    let behind = spawn_creature(&mut app, Vec2::new(350.0, 300.0), test_genes(true), true);
+    app.update();
+
+    let perception = app.world().get::<Perception>(prey).unwrap();
+    assert!(perception.visible_predators.contains(&ahead));
+    assert!(!perception.visible_predators.contains(&behind));
+    assert_eq!(perception.threats.len(), 1);
+}
+
+#[test]
+fn a_hungry_predator_hunts_down_prey_and_eats() {
+    let mut app = empty_app();
+    let mut genes = test_genes(true);
+    genes.field_of_view = std::f32::consts::TAU;
+    genes.aggression = 1.0; // hunts as soon as it is a little hungry
+    genes.chase_speed = 20.0;
+    genes.attack_range = 3.0;
+    let predator = spawn_creature(&mut app, Vec2::new(400.0, 300.0), genes, true);
+    app.world_mut().get_mut::<Needs>(predator).unwrap().hunger = 50.0;
+    let mut genes = test_genes(false);
+    genes.max_speed = 1.0;
+    let prey = spawn_creature(&mut app, Vec2::new(430.0, 300.0), genes, false);
+    // full grown, a newborn is hardly a meal
+    app.world_mut().get_mut::<Age>(prey).unwrap().0 = genes.max_age / 2.0;
+    app.update();
+    let prey_id = *app.world().get::<IndividualId>(prey).unwrap();
+    for _ in 0..300 {
+        app.update();
+        if app.world().get_entity(prey).is_none() {
+            break;
+        }
+    }
+
+    assert!(app.world().get_entity(prey).is_none());
+    let ledger = app.world().resource::<LineageLedger>();
+    assert!(ledger.get(prey_id).unwrap().died.is_some());
+    assert!(app.world().get::<Needs>(predator).unwrap().hunger < 50.0);
+}
+
+#[test]
+fn scared_prey_flee_from_a_predator() {
+    let mut app = empty_app();
+    let mut genes = test_genes(false);
+    genes.field_of_view = std::f32::consts::TAU;
+    genes.vision_range = 300.0;
+    genes.boldness = 0.0;
+    genes.panic_threshold = 0.1;
+    let prey = spawn_creature(&mut app, Vec2::new(400.0, 300.0), genes, false);
+    // a fed predator that barely moves, only its sight scares the prey
+    let mut genes = test_genes(true);
+    genes.max_speed = 0.1;
+    let predator = spawn_creature(&mut app, Vec2::new(440.0, 300.0), genes, true);
+    for _ in 0..60 {
+        app.update();
+    }
+
+    let world = app.world();
+    assert!(world.get::<BehaviorState>(prey) == Some(&BehaviorState::Flee));
+    assert!(world.get::<Needs>(prey).unwrap().fear > 0.1);
+    let distance = world
+        .get::<Position>(prey)
+        .unwrap()
+        .0
+        .distance(world.get::<Position>(predator).unwrap().0);
+    assert!(distance > 40.0);
+}
+
+#[test]
+fn starving_prey_scavenge_and_rotten_flesh_costs_sanity() {
+    let mut app = empty_app();
+    let mut genes = test_genes(false);
+    genes.vision_range = 100.0;
+    // one corpse next to each prey, the first has almost rotted away
+    let mut scavengers = Vec::new();
+    for (x, decay_timer) in [(200.0, 10.0), (600.0, 100.0)] {
+        let prey = spawn_creature(&mut app, Vec2::new(x, 300.0), genes, false);
+        // hungry enough to eat flesh, not so hungry that it loses sanity anyway
+        app.world_mut().get_mut::<Needs>(prey).unwrap().hunger = 88.0;
+        app.world_mut().spawn(create_corpse(
+            Vec2::new(x + 1.0, 300.0),
+            50.0,
+            decay_timer,
+            1.0,
+        ));
+        scavengers.push(prey);
+    }
+    for _ in 0..30 {
+        app.update();
+    }
+
+    let rotten = app.world().get::<Needs>(scavengers[0]).unwrap();
+    let fresh = app.world().get::<Needs>(scavengers[1]).unwrap();
+    assert!(rotten.hunger < 88.0);
+    assert!(fresh.hunger < 88.0);
+    assert!(rotten.sanity < fresh.sanity);
+}
+
+#[test]
+fn eaten_plants_regrow_up_to_their_max_amount() {
+    let mut app = empty_app();
+    let growing = app
+        .world_mut()
+        .spawn(create_plant(Vec2::new(200.0, 300.0), 50.0, None))
+        .id();
+    let nearly_full = app
+        .world_mut()
+        .spawn(create_plant(Vec2::new(600.0, 300.0), 50.0, None))
+        .id();
+    app.world_mut().get_mut::<FoodAmount>(growing).unwrap().0 = 10.0;
+    app.world_mut()
+        .get_mut::<FoodAmount>(nearly_full)
+        .unwrap()
+        .0 = 49.9;
+    let start = app.world().resource::<SimulationClock>().0;
+    for _ in 0..60 {
+        app.update();
+    }
+
+    let elapsed = app.world().resource::<SimulationClock>().0 - start;
+    let regrow_rate = app.world().resource::<SimulationParams>().plant_regrow_rate;
+    let amount = app.world().get::<FoodAmount>(growing).unwrap().0;
+    assert!((amount - (10.0 + regrow_rate * elapsed)).abs() < 1e-3);
+    assert_eq!(app.world().get::<FoodAmount>(nearly_full).unwrap().0, 50.0);
+}
+
+#[test]
+fn seeds_planted_in_a_full_forest_do_not_spread_it() {
+    let mut app = empty_app();
+    let center = Vec2::new(400.0, 300.0);
+    app.world_mut().spawn(ForestArea {
+        id: 7,
+        center,
+        half_size: 50.0,
+        capacity: 2,
+    });
+    // a young plant, too small to spread seeds itself
+    app.world_mut()
+        .spawn(create_plant(center, 50.0, Some(7)))
+        .get_mut::<FoodAmount>()
+        .unwrap()
+        .0 = 1.0;
+    // two seeds planted in the same step, with room for only one of them
+    for x in [390.0, 410.0] {
+        app.world_mut().spawn((
+            Position(Vec2::new(x, 300.0)),
+            Needs::default(),
+            CarriedSeed {
+                area: Some(7),
+                max_amount: 50.0,
+                timer: 0.0,
+            },
+        ));
+    }
+    app.update();
+
+    let mut query = app.world_mut().query::<&Plant>();
+    let areas: Vec<Option<u32>> = query.iter(app.world()).map(|plant| plant.area).collect();
+    assert_eq!(areas.len(), 3);
+    assert_eq!(areas.iter().filter(|&&area| area == Some(7)).count(), 2);
+    assert_eq!(areas.iter().filter(|&&area| area.is_none()).count(), 1);
+}
+
+#[test]
+fn a_behavior_only_changes_when_another_scores_higher_by_the_margin() {
+    let mut app = empty_app();
+    let mut genes = test_genes(false);
+    genes.greed = 1.0;
+    let prey = spawn_creature(&mut app, Vec2::new(400.0, 300.0), genes, false);
+    app.world_mut().get_mut::<Needs>(prey).unwrap().hunger = 70.0;
+    set_params(
+        &mut app,
+        SimulationParams {
+            behavior_hysteresis: 10.0,
+            ..Default::default()
+        },
+    );
+    for _ in 0..10 {
+        app.update();
+    }
+    // it wants food more, but not by enough
+    let scores = app.world().get::<BehaviorScores>(prey).unwrap();
+    assert!(scores.seek_food > scores.wander);
+    assert!(app.world().get::<BehaviorState>(prey) == Some(&BehaviorState::Wander));
+
+    set_params(&mut app, SimulationParams::default());
+    app.update();
+    assert!(app.world().get::<BehaviorState>(prey) == Some(&BehaviorState::SeekFood));
+}
+
+#[test]
+fn a_tired_prey_sleeps_until_a_predator_comes_close() {
+    let mut app = empty_app();
+    let mut genes = test_genes(false);
+    genes.laziness = 0.5;
+    genes.curiosity = 0.0;
+    genes.sleep_duration = 100.0;
+    genes.boldness = 0.0;
+    let prey = spawn_creature(&mut app, Vec2::new(400.0, 300.0), genes, false);
+    app.world_mut().get_mut::<Needs>(prey).unwrap().energy = 0.05;
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let world = app.world();
+    assert!(world.get::<BehaviorState>(prey) == Some(&BehaviorState::Sleep));
+    assert_eq!(world.get::<Velocity>(prey).unwrap().0, Vec2::ZERO);
+    let energy = world.get::<Needs>(prey).unwrap().energy;
+    assert!(energy > 0.05);
+
+    // a sleeper doesn't look around, but one right next to it scares it awake
+    let mut genes = test_genes(true);
+    genes.max_speed = 0.1;
+    spawn_creature(&mut app, Vec2::new(410.0, 300.0), genes, true);
+    for _ in 0..30 {
+        app.update();
+    }
+    assert!(app.world().get::<BehaviorState>(prey) == Some(&BehaviorState::Flee));
+}
+
+#[test]
+fn a_short_sighted_sleeper_is_scared_by_what_wakes_it() {
+    let mut app = empty_app();
+    let mut genes = test_genes(false);
+    genes.laziness = 0.5;
+    genes.curiosity = 0.0;
+    genes.sleep_duration = 100.0;
+    genes.boldness = 0.0;
+    genes.vision_range = 10.0; // sees less far than a predator wakes it
+    let prey = spawn_creature(&mut app, Vec2::new(400.0, 300.0), genes, false);
+    app.world_mut().get_mut::<Needs>(prey).unwrap().energy = 0.05;
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(app.world().get::<BehaviorState>(prey) == Some(&BehaviorState::Sleep));
+
+    let mut genes = test_genes(true);
+    genes.max_speed = 0.1;
+    spawn_creature(&mut app, Vec2::new(415.0, 300.0), genes, true);
+    app.update();
+    let world = app.world();
+    let perception = world.get::<Perception>(prey).unwrap();
+    assert_eq!(perception.threats.len(), 1);
+    assert!(perception.threats[0].1 > 0.0);
+    assert!(world.get::<Needs>(prey).unwrap().fear > 0.0);
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use life_sim_2d::entities::components::SpatialGrid;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn random_grid(rng: &mut StdRng) -> (SpatialGrid, Vec<(Entity, Vec2)>) {
+    let mut grid = SpatialGrid {
+        cell_size: 64.0,
+        ..Default::default()
+    };
+    let points: Vec<(Entity, Vec2)> = (0..500)
+        .map(|i| {
+            let pos = Vec2::new(rng.gen_range(-300.0..300.0), rng.gen_range(-300.0..300.0));
+            (Entity::from_raw(i), pos)
+        })
+        .collect();
+    for &(entity, pos) in points.iter() {
+        grid.insert(entity, pos);
+    }
+    (grid, points)
+}
+
+#[test]
+fn within_radius_finds_exactly_the_points_in_range() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let (grid, points) = random_grid(&mut rng);
+    for _ in 0..50 {
+        let pos = Vec2::new(rng.gen_range(-300.0..300.0), rng.gen_range(-300.0..300.0));
+        let radius = rng.gen_range(0.0..250.0);
+        let mut found: Vec<Entity> = grid
+            .within_radius(pos, radius)
+            .map(|(entity, ..)| entity)
+            .collect();
+        let mut expected: Vec<Entity> = points
+            .iter()
+            .filter(|(_, other)| pos.distance(*other) <= radius)
+            .map(|(entity, _)| *entity)
+            .collect();
+        found.sort();
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn nearest_matches_a_full_search() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let (grid, points) = random_grid(&mut rng);
+    for _ in 0..50 {
+        let pos = Vec2::new(rng.gen_range(-300.0..300.0), rng.gen_range(-300.0..300.0));
+        let radius = rng.gen_range(0.0..250.0);
+        // only every third point counts
+        let wanted = |entity: Entity| entity.index() % 3 == 0;
+        let found = grid
+            .nearest(pos, radius, |entity, _| wanted(entity))
+            .map(|(entity, ..)| entity);
+        let expected = points
+            .iter()
+            .filter(|(entity, other)| wanted(*entity) && pos.distance(*other) <= radius)
+            .min_by(|a, b| pos.distance(a.1).total_cmp(&pos.distance(b.1)))
+            .map(|(entity, _)| *entity);
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+use life_sim_2d::entities::steering::{Kinematics, arrive, flee, seek, separation, steer};
+
+const LIMITS: Kinematics = Kinematics {
+    max_speed: 10.0,
+    max_acceleration: 20.0,
+    max_turn_rate: 1.0,
+};
+
+fn assert_close(a: Vec2, b: Vec2) {
+    assert!(a.distance(b) < 1e-4, "{} is not {}", a, b);
+}
+
+#[test]
+fn seek_and_flee_want_full_speed_along_the_line() {
+    let pos = Vec2::new(1.0, 1.0);
+    let velocity = Vec2::new(0.0, 2.0);
+    let target = Vec2::new(4.0, 5.0); // 3, 4, 5 triangle
+    let toward = Vec2::new(0.6, 0.8) * 10.0;
+    assert_close(seek(pos, velocity, target, 10.0), toward - velocity);
+    assert_close(flee(pos, velocity, target, 10.0), -toward - velocity);
+    // nowhere to go when already there
+    assert_close(seek(pos, velocity, pos, 10.0), -velocity);
+}
+
+#[test]
+fn arrive_slows_down_inside_the_slowing_radius() {
+    let target = Vec2::new(10.0, 0.0);
+    // outside the radius it is a seek
+    assert_close(
+        arrive(Vec2::ZERO, Vec2::ZERO, target, 8.0, 5.0),
+        seek(Vec2::ZERO, Vec2::ZERO, target, 8.0),
+    );
+    // halfway into the radius it wants half the speed
+    let pos = Vec2::new(7.5, 0.0);
+    assert_close(
+        arrive(pos, Vec2::ZERO, target, 8.0, 5.0),
+        Vec2::new(4.0, 0.0),
+    );
+    // on the target it only brakes
+    let velocity = Vec2::new(1.0, 1.0);
+    assert_close(arrive(target, velocity, target, 8.0, 5.0), -velocity);
+}
+
+#[test]
+fn arrive_without_a_slowing_radius_is_a_seek() {
+    let pos = Vec2::new(7.5, 0.0);
+    let target = Vec2::new(10.0, 0.0);
+    let seek_force = seek(pos, Vec2::ZERO, target, 8.0);
+    assert_close(arrive(pos, Vec2::ZERO, target, 8.0, 0.0), seek_force);
+    assert_close(arrive(pos, Vec2::ZERO, target, 8.0, -5.0), seek_force);
+}
+
+#[test]
+fn separation_pushes_harder_the_closer_a_neighbor_is() {
+    let neighbors = [
+        Vec2::new(1.0, 0.0),
+        Vec2::new(0.0, -3.0),
+        Vec2::new(9.0, 9.0),
+    ];
+    // the last one is out of range
+    assert_close(
+        separation(Vec2::ZERO, &neighbors, 4.0),
+        Vec2::new(-0.75, 0.25),
+    );
+    assert_close(separation(Vec2::ZERO, &[], 4.0), Vec2::ZERO);
+}
+
+#[test]
+fn steer_keeps_to_the_acceleration_turn_and_speed_limits() {
+    // a huge force only adds the acceleration limit
+    let velocity = steer(Vec2::ZERO, Vec2::new(1000.0, 0.0), LIMITS, 0.1);
+    assert_close(velocity, Vec2::new(2.0, 0.0));
+
+    // a sharp turn is cut to the turn rate
+    let velocity = Vec2::new(5.0, 0.0);
+    let turned = steer(velocity, Vec2::new(-5.0, 20.0), LIMITS, 0.1);
+    assert!((velocity.angle_between(turned) - 0.1).abs() < 1e-4);
+
+    // and the result never goes faster than the speed limit
+    let fast = steer(Vec2::new(9.5, 0.0), Vec2::new(20.0, 0.0), LIMITS, 0.5);
+    assert_close(fast, Vec2::new(10.0, 0.0));
+}